@@ -0,0 +1,828 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::rc::Rc;
+use crate::expr;
+use crate::expr::{Expr, ExprVisitor, ValType, FnDef, FnBody, Pattern, Arg, Param, MethodSig, NodeId};
+use crate::stmt;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::token::{Token, TokenType};
+use crate::resolver::Depths;
+
+//works out a type for every expression it can and reports every mismatch
+//that's certain to be an error when it runs, before anything does run.
+//a type it can't work out (e.g. a field, or what a generic function
+//returns) is None and is left for the interpreter to check. scopes are
+//the same as the resolver's, so a variable's depth says where it is
+
+//what's known about a name from its declaration
+#[derive (Clone)]
+enum Known {
+    Unknown,
+    Var(ValType),
+    //declared with function, so calls to it can be checked
+    Func(Rc<FnDef>),
+    //calling it makes an instance
+    Class(Rc<String>),
+}
+
+impl Known {
+    fn val_type(&self) -> Option<ValType> {
+	match self {
+	    Known::Unknown => None,
+	    Known::Var(t) => Some(t.clone()),
+	    Known::Func(_) => Some(ValType::Fn),
+	    Known::Class(_) => Some(ValType::Class),
+	}
+    }
+}
+
+type Scope = HashMap<Rc<String>, Known>;
+
+struct ClassInfo {
+    superclass: Option<Rc<String>>,
+    interfaces: Vec<Rc<String>>,
+    methods: Vec<Rc<FnDef>>,
+}
+
+pub struct Checker {
+    //the first scope is the globals
+    scopes: Vec<Scope>,
+    //kept between lines at the prompt
+    globals: Scope,
+    classes: HashMap<Rc<String>, ClassInfo>,
+    //every interface declared so far, for checking implements
+    interfaces: HashMap<Rc<String>, Vec<Rc<MethodSig>>>,
+    //the functions being checked, innermost last, for return
+    functions: Vec<Rc<FnDef>>,
+    //the classes whose methods are being checked, for self
+    in_class: Vec<Rc<String>>,
+    //where the resolver found each local variable
+    depths: Depths,
+    had_error: bool,
+}
+
+impl Checker {
+    pub fn new() -> Self {
+	Checker {
+	    scopes: Vec::new(),
+	    globals: Scope::new(),
+	    classes: HashMap::new(),
+	    interfaces: HashMap::new(),
+	    functions: Vec::new(),
+	    in_class: Vec::new(),
+	    depths: Depths::new(),
+	    had_error: false,
+	}
+    }
+
+    pub fn check(&mut self, ast: &[Stmt], depths: &Depths) -> Result<(), Box<dyn Error>> {
+	self.had_error = false;
+	self.depths = depths.clone();
+	self.scopes = vec![self.globals.clone()];
+	for stmt in ast.iter() {
+	    stmt.accept(self);
+	}
+	let globals = self.scopes.pop().expect("no globals, fix checker::check");
+	if self.had_error {
+	    return Err(Box::new(CheckError {}));
+	}
+	self.globals = globals;
+	Ok(())
+    }
+
+    fn error(&mut self, at: &Token, msg: &str) {
+	crate::report(at.line, &format!(" at '{}'", at.lexeme), msg);
+	self.had_error = true;
+    }
+
+    fn type_of(&mut self, e: &Expr) -> Option<ValType> {
+	match e.accept(self) {
+	    //a type parameter could be anything until the call decides
+	    Some(ValType::Param(_)) => None,
+	    t => t,
+	}
+    }
+
+    fn define(&mut self, name: &Token, known: Known) {
+	self.scopes.last_mut().expect("no scope, fix checker::define").insert(name.lexeme.clone(), known);
+    }
+
+    //what's known about the variable name that the node id uses
+    fn lookup(&self, id: NodeId, name: &Token) -> Known {
+	let scope = match self.depths.get(&id) {
+	    Some(d) => match self.scopes.len().checked_sub(d.scopes + 1) {
+		Some(i) => &self.scopes[i],
+		None => return Known::Unknown,
+	    },
+	    None => &self.scopes[0],
+	};
+	scope.get(&name.lexeme).cloned().unwrap_or(Known::Unknown)
+    }
+
+    //Some(false) only when the whole of class's hierarchy is known and name isn't in it
+    fn is_a(&self, class: &Rc<String>, name: &Rc<String>) -> Option<bool> {
+	let mut class = class.clone();
+	for _ in 0..=self.classes.len() {
+	    if class == *name {
+		return Some(true);
+	    }
+	    let info = self.classes.get(&class)?;
+	    if info.interfaces.contains(name) {
+		return Some(true);
+	    }
+	    match &info.superclass {
+		Some(s) => class = s.clone(),
+		None => return Some(false),
+	    }
+	}
+	None
+    }
+
+    //Some(None) when the method certainly isn't there
+    fn find_method(&self, class: &Rc<String>, name: &str) -> Option<Option<Rc<FnDef>>> {
+	let mut class = class.clone();
+	for _ in 0..=self.classes.len() {
+	    let info = self.classes.get(&class)?;
+	    if let Some(m) = info.methods.iter().find(|m| m.name.as_ref().is_some_and(|n| *n.lexeme == name)) {
+		return Some(Some(m.clone()));
+	    }
+	    match &info.superclass {
+		Some(s) => class = s.clone(),
+		None => return Some(None),
+	    }
+	}
+	None
+    }
+
+    //whether a value of type actual passes where expect is declared, the same
+    //as Value::is except that anything not known here is given the benefit of the doubt
+    fn fits(&self, expect: &ValType, actual: &ValType) -> bool {
+	match (expect, actual) {
+	    (ValType::Param(_), _) | (_, ValType::Param(_)) => true,
+	    //a variable of a superclass can hold the subclass, so only classes
+	    //that can't be each other are certain not to fit
+	    (ValType::Object(n), ValType::Object(m)) => self.is_a(m, n) != Some(false) || self.is_a(n, m) != Some(false),
+	    (ValType::List(Some(t)), ValType::List(Some(u))) => self.fits(t, u),
+	    (ValType::List(_), ValType::List(_)) => true,
+	    (e, a) => e == a,
+	}
+    }
+
+    //every method the interface asks for, with the same parameter and return types
+    fn check_implements(&mut self, class: &Token, interface: &Token) {
+	let sigs = match self.interfaces.get(&interface.lexeme) {
+	    Some(sigs) => sigs.clone(),
+	    None => {
+		self.error(interface, &format!("undefined interface '{}'", interface.lexeme));
+		return;
+	    },
+	};
+	for sig in sigs.iter() {
+	    match self.find_method(&class.lexeme, &sig.name.lexeme) {
+		Some(Some(def)) if sig.matches(&def) => {},
+		Some(Some(def)) => {
+		    let at = def.name.as_ref().unwrap_or(class);
+		    self.error(at, &format!("method '{}' doesn't match interface '{}', expect {}",
+					    sig.name.lexeme, interface.lexeme, sig.signature()));
+		},
+		Some(None) => {
+		    self.error(class, &format!("class '{}' is missing method '{}' of interface '{}'",
+					       class.lexeme, sig.name.lexeme, interface.lexeme));
+		},
+		//a superclass that isn't known here could have it
+		None => {},
+	    }
+	}
+    }
+
+    //what a call returns, when that doesn't depend on the call
+    fn returns(def: &FnDef) -> Option<ValType> {
+	match &def.ret {
+	    Some(t) if t.type_param().is_none() => Some(t.clone()),
+	    _ => None,
+	}
+    }
+
+    //arguments whose types are known against the parameters they're for,
+    //anything to do with how many there are is left to the parser and interpreter.
+    //callee is the name the call uses, for the messages
+    fn check_args(&mut self, callee: &Token, def: &FnDef, args: &[(Option<ValType>, &Arg)]) {
+	let mut decided = HashMap::new();
+	let mut positional = 0;
+	for (t, arg) in args.iter() {
+	    let param = match &arg.name {
+		Some(n) => def.params.iter().find(|p| p.name.lexeme == n.lexeme),
+		None => {
+		    let p = def.params.get(positional).or(def.params.last().filter(|p| p.variadic));
+		    if p.is_some_and(|p| !p.variadic) {
+			positional += 1;
+		    }
+		    p
+		},
+	    };
+	    let (param, t) = match (param, t) {
+		(Some(p), Some(t)) => (p, t),
+		_ => continue,
+	    };
+	    match &param.p_type {
+		ValType::Param(_) => self.decide(callee, def, param, t, &arg.start, &mut decided),
+		p if p.type_param().is_none() && !self.fits(p, t) => {
+		    self.error(&arg.start, &format!("argument '{}' should be {p} but got {t}", param.name.lexeme));
+		},
+		_ => {},
+	    }
+	}
+    }
+
+    //the first argument for a type parameter decides what it is in the call
+    //and the rest have to agree, except instances, which the interpreter
+    //widens to the class they have in common
+    fn decide(&mut self, callee: &Token, def: &FnDef, param: &Param, actual: &ValType,
+	      at: &Token, decided: &mut HashMap<Rc<String>, ValType>) {
+	let tp = match &param.p_type {
+	    ValType::Param(tp) => tp,
+	    _ => return,
+	};
+	match decided.get(tp) {
+	    Some(ValType::Object(_)) if matches!(actual, ValType::Object(_)) => {},
+	    Some(was) if was != actual => {
+		let msg = format!("argument '{}' should be {was}, since {tp} is {was} in this call to '{}', but got {actual}",
+				  param.name.lexeme, callee.lexeme);
+		self.error(at, &msg);
+	    },
+	    Some(_) => {},
+	    None => {
+		let bound = def.type_params.iter()
+		    .find(|p| p.name.lexeme == *tp)
+		    .and_then(|p| p.bound.as_ref());
+		if let Some(b) = bound
+		    && !self.fits(b, actual) {
+		    self.error(at, &format!("{tp} is {actual} in this call to '{}', which isn't {b}", callee.lexeme));
+		}
+		decided.insert(tp.clone(), actual.clone());
+	    },
+	}
+    }
+
+    fn condition(&mut self, k: &Token, c: &Expr, msg: &str) {
+	match self.type_of(c) {
+	    Some(ValType::Bool) | None => {},
+	    Some(t) => self.error(k, &format!("{msg}, not {t}")),
+	}
+    }
+
+    fn scoped(&mut self, stmts: &[Stmt], name: Option<(&Token, Known)>) {
+	self.scopes.push(Scope::new());
+	if let Some((n, k)) = name {
+	    self.define(n, k);
+	}
+	for stmt in stmts.iter() {
+	    stmt.accept(self);
+	}
+	self.scopes.pop();
+    }
+
+    fn function(&mut self, def: &Rc<FnDef>) {
+	self.scopes.push(Scope::new());
+	self.functions.push(def.clone());
+	for p in def.params.iter() {
+	    if let Some(d) = &p.default {
+		match self.type_of(d.as_ref()) {
+		    Some(t) if p.p_type.type_param().is_none() && !self.fits(&p.p_type, &t) => {
+			self.error(&p.name, &format!("default for '{}' should be {} but is {t}", p.name.lexeme, p.p_type));
+		    },
+		    _ => {},
+		}
+	    }
+	    let t = match p.variadic {
+		true => ValType::List(Some(Box::new(p.p_type.clone()))),
+		false => p.p_type.clone(),
+	    };
+	    self.define(&p.name, Known::Var(t));
+	}
+	match &def.body {
+	    FnBody::Expr(b) => {
+		let t = self.type_of(b.as_ref());
+		self.check_return(t);
+	    },
+	    FnBody::Block(stmts) => {
+		for stmt in stmts.iter() {
+		    stmt.accept(self);
+		}
+	    },
+	}
+	self.functions.pop();
+	self.scopes.pop();
+    }
+
+    //a value of type t returned from the innermost function
+    fn check_return(&mut self, t: Option<ValType>) {
+	let def = match self.functions.last() {
+	    Some(def) => def.clone(),
+	    None => return,
+	};
+	let expect = match Checker::returns(&def) {
+	    Some(e) => e,
+	    None => return,
+	};
+	let name = match &def.name {
+	    Some(n) => format!("'{}'", n.lexeme),
+	    None => "anonymous function".to_string(),
+	};
+	match t {
+	    Some(t) if !self.fits(&expect, &t) => {
+		self.error(&def.keyword, &format!("function {name} should return {expect} but returns {t}"));
+	    },
+	    _ => {},
+	}
+    }
+
+    fn bind_pattern(&mut self, p: &Pattern, t: Option<ValType>) {
+	match p {
+	    Pattern::Name(Some(expect), n, _) => {
+		match t {
+		    Some(t) if !self.fits(expect, &t) => {
+			self.error(n, &format!("can't initialize {expect} '{}' with {t}", n.lexeme));
+		    },
+		    _ => {},
+		}
+		self.define(n, Known::Var(expect.clone()));
+	    },
+	    Pattern::Name(None, n, _) => {
+		self.define(n, t.map(Known::Var).unwrap_or(Known::Unknown));
+	    },
+	    //a tuple's type doesn't say what's in it
+	    Pattern::Tuple(_, items) => {
+		for i in items.iter() {
+		    self.bind_pattern(i, None);
+		}
+	    },
+	}
+    }
+
+    //operators on an instance need the class to have the method, or for
+    //the comparisons, the methods they're worked out from
+    fn overloaded(&mut self, op: &Token, class: &Rc<String>, right: Option<ValType>) -> Option<ValType> {
+	let compare = !matches!(op.t_type, TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash);
+	let method = match self.find_method(class, &op.lexeme) {
+	    Some(m) => m,
+	    None => return if compare { Some(ValType::Bool) } else { None },
+	};
+	match method {
+	    Some(def) => {
+		//== and != with anything else the method doesn't take are whether they're the same value
+		let identity = matches!(op.t_type, TokenType::EqualEqual | TokenType::BangEqual);
+		match (def.params.first(), &right) {
+		    (Some(p), Some(r)) if !identity && p.p_type.type_param().is_none() && !self.fits(&p.p_type, r) => {
+			self.error(op, &format!("argument '{}' should be {} but got {r}", p.name.lexeme, p.p_type));
+		    },
+		    _ => {},
+		}
+		if compare { Some(ValType::Bool) } else { Checker::returns(&def) }
+	    },
+	    None => {
+		let fallback = match op.t_type {
+		    TokenType::EqualEqual | TokenType::BangEqual => true,
+		    TokenType::Less | TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash => false,
+		    _ => !matches!(self.find_method(class, "<"), Some(None)),
+		};
+		if !fallback {
+		    self.error(op, &format!("operator '{}' isn't defined for {class}", op.lexeme));
+		}
+		if compare { Some(ValType::Bool) } else { None }
+	    },
+	}
+    }
+}
+
+fn arithmetic(op: &Token) -> bool {
+    matches!(op.t_type, TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash)
+}
+
+//the built in types each binary operator works on, both sides have to be the same one
+fn operands(op: &Token) -> &'static [ValType] {
+    match op.t_type {
+	TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash => &[ValType::Int, ValType::Real],
+	TokenType::EqualEqual => &[ValType::Int, ValType::Real, ValType::Char, ValType::Bool, ValType::Str, ValType::Nil],
+	TokenType::BangEqual => &[ValType::Int, ValType::Real, ValType::Char, ValType::Bool, ValType::Str],
+	_ => &[ValType::Int, ValType::Real, ValType::Char],
+    }
+}
+
+impl ExprVisitor<Option<ValType>> for Checker {
+    fn visit_binary(&mut self, e: &expr::Binary) -> Option<ValType> {
+	let left = self.type_of(e.left.as_ref());
+	let right = self.type_of(e.right.as_ref());
+	match &left {
+	    Some(ValType::Object(c)) => self.overloaded(&e.operator, c, right),
+	    Some(l) if !operands(&e.operator).contains(l) => {
+		match &right {
+		    Some(r) => self.error(&e.operator, &format!("can't apply '{}' to {l} and {r}", e.operator.lexeme)),
+		    None => self.error(&e.operator, &format!("can't apply '{}' to {l}", e.operator.lexeme)),
+		}
+		None
+	    },
+	    Some(l) => {
+		match &right {
+		    Some(r) if r != l => {
+			self.error(&e.operator, &format!("can't apply '{}' to {l} and {r}", e.operator.lexeme));
+		    },
+		    _ => {},
+		}
+		match arithmetic(&e.operator) {
+		    true => Some(l.clone()),
+		    false => Some(ValType::Bool),
+		}
+	    },
+	    None if arithmetic(&e.operator) => None,
+	    None => Some(ValType::Bool),
+	}
+    }
+
+    fn visit_unary(&mut self, e: &expr::Unary) -> Option<ValType> {
+	let expect = match e.operator.t_type {
+	    TokenType::Minus => ValType::Real,
+	    _ => ValType::Bool,
+	};
+	match self.type_of(e.right.as_ref()) {
+	    Some(t) if t != expect => self.error(&e.operator, &format!("can't apply '{}' to {t}", e.operator.lexeme)),
+	    _ => {},
+	}
+	Some(expect)
+    }
+
+    fn visit_grouping(&mut self, e: &expr::Grouping) -> Option<ValType> {
+	self.type_of(e.expression.as_ref())
+    }
+
+    fn visit_literal(&mut self, e: &expr::Literal) -> Option<ValType> {
+	use expr::Lit;
+	Some(match e.value {
+	    Lit::StrLit(_) => ValType::Str,
+	    Lit::RealLit(_) => ValType::Real,
+	    Lit::IntLit(_) => ValType::Int,
+	    Lit::CharLit(_) => ValType::Char,
+	    Lit::BoolLit(_) => ValType::Bool,
+	    Lit::NilLit => ValType::Nil,
+	})
+    }
+
+    //a variable keeps the type it was declared with, though an
+    //object variable can take an instance of any class
+    fn visit_assignment(&mut self, e: &expr::Assignment) -> Option<ValType> {
+	let t = self.type_of(e.val.as_ref());
+	match (self.lookup(e.node.id, &e.name).val_type(), &t) {
+	    (Some(ValType::Param(_)), _) => {},
+	    (Some(l), Some(r)) if std::mem::discriminant(&l) != std::mem::discriminant(r) => {
+		self.error(&e.name, &format!("can't assign {r} to {l} '{}'", e.name.lexeme));
+	    },
+	    _ => {},
+	}
+	t
+    }
+
+    fn visit_variable(&mut self, e: &expr::Variable) -> Option<ValType> {
+	self.lookup(e.node.id, &e.name).val_type()
+    }
+
+    fn visit_logical(&mut self, e: &expr::Logical) -> Option<ValType> {
+	for side in [&e.left, &e.right] {
+	    match self.type_of(side.as_ref()) {
+		Some(ValType::Bool) | None => {},
+		Some(t) => self.error(&e.operator, &format!("logical operand must be boolean, not {t}")),
+	    }
+	}
+	Some(ValType::Bool)
+    }
+
+    fn visit_cast(&mut self, e: &expr::Cast) -> Option<ValType> {
+	let to = match e.target.t_type {
+	    TokenType::Int => ValType::Int,
+	    _ => ValType::Char,
+	};
+	match self.type_of(e.expr.as_ref()) {
+	    Some(ValType::Int) | Some(ValType::Char) | None => {},
+	    Some(t) => self.error(&e.target, &format!("cannot convert {t} to {to}")),
+	}
+	Some(to)
+    }
+
+    fn visit_conditional(&mut self, e: &expr::Conditional) -> Option<ValType> {
+	self.condition(&e.keyword, e.cond.as_ref(), "conditional expression must be boolean");
+	let then_type = self.type_of(e.then_expr.as_ref());
+	let else_type = self.type_of(e.else_expr.as_ref());
+	match (&then_type, &else_type) {
+	    (Some(t), Some(f)) if std::mem::discriminant(t) != std::mem::discriminant(f) => {
+		self.error(&e.keyword, &format!("conditional branches must have the same type, not {t} and {f}"));
+	    },
+	    _ => {},
+	}
+	match then_type == else_type {
+	    true => then_type,
+	    false => None,
+	}
+    }
+
+    fn visit_lambda(&mut self, e: &expr::Lambda) -> Option<ValType> {
+	self.function(&e.def);
+	Some(ValType::Fn)
+    }
+
+    fn visit_call(&mut self, e: &expr::Call) -> Option<ValType> {
+	//a call by name gets what's known about the name, not just its type
+	let (known, callee) = match e.callee.as_ref() {
+	    Expr::Variable(v) => {
+		let _ = self.type_of(e.callee.as_ref());
+		(self.lookup(e.callee.id(), &v.name), &v.name)
+	    },
+	    _ => (self.type_of(e.callee.as_ref()).map(Known::Var).unwrap_or(Known::Unknown), &e.paren),
+	};
+	let mut args = Vec::new();
+	for arg in e.args.iter() {
+	    args.push((self.type_of(arg.value.as_ref()), arg));
+	}
+	match known {
+	    Known::Func(def) => {
+		self.check_args(callee, &def, &args);
+		Checker::returns(&def)
+	    },
+	    Known::Class(c) => {
+		if let Some(Some(init)) = self.find_method(&c, "init") {
+		    self.check_args(callee, &init, &args);
+		}
+		Some(ValType::Object(c))
+	    },
+	    Known::Var(ValType::Fn) | Known::Var(ValType::Class) | Known::Var(ValType::Param(_)) | Known::Unknown => None,
+	    Known::Var(t) => {
+		self.error(&e.paren, &format!("can't call a value of type {t}"));
+		None
+	    },
+	}
+    }
+
+    fn visit_index(&mut self, e: &expr::Index) -> Option<ValType> {
+	let object = self.type_of(e.object.as_ref());
+	match self.type_of(e.index.as_ref()) {
+	    Some(ValType::Int) | None => {},
+	    Some(t) => self.error(&e.bracket, &format!("index must be int, not {t}")),
+	}
+	match object {
+	    Some(ValType::List(Some(t))) => Some(*t),
+	    Some(ValType::Str) => Some(ValType::Char),
+	    Some(ValType::List(None)) | Some(ValType::Tuple) | None => None,
+	    Some(t) => {
+		self.error(&e.bracket, &format!("can't index a value of type {t}"));
+		None
+	    },
+	}
+    }
+
+    fn visit_range(&mut self, e: &expr::Range) -> Option<ValType> {
+	for b in [Some(&e.start), Some(&e.end), e.step.as_ref()].into_iter().flatten() {
+	    match self.type_of(b.as_ref()) {
+		Some(ValType::Int) | None => {},
+		Some(t) => self.error(&e.operator, &format!("range bounds must be int, not {t}")),
+	    }
+	}
+	Some(ValType::Range)
+    }
+
+    fn visit_tuple(&mut self, e: &expr::Tuple) -> Option<ValType> {
+	for i in e.items.iter() {
+	    self.type_of(i);
+	}
+	Some(ValType::Tuple)
+    }
+
+    //a list literal whose items are all known to be the same type is a list of that type
+    fn visit_list(&mut self, e: &expr::List) -> Option<ValType> {
+	let types: Vec<Option<ValType>> = e.items.iter().map(|i| self.type_of(i)).collect();
+	let item = match types.first() {
+	    Some(Some(t)) if types.iter().all(|u| u.as_ref() == Some(t)) => Some(Box::new(t.clone())),
+	    _ => None,
+	};
+	Some(ValType::List(item))
+    }
+
+    fn visit_destructure(&mut self, e: &expr::Destructure) -> Option<ValType> {
+	self.type_of(e.val.as_ref())
+    }
+
+    //fields come and go, so only what isn't an object at all is an error
+    fn visit_get(&mut self, e: &expr::Get) -> Option<ValType> {
+	match self.type_of(e.object.as_ref()) {
+	    Some(ValType::Object(_)) | None => None,
+	    Some(ValType::Err) => match e.name.lexeme.as_str() {
+		"kind" | "msg" => Some(ValType::Str),
+		"line" => Some(ValType::Int),
+		_ => {
+		    self.error(&e.name, &format!("an error has no property {}", e.name.lexeme));
+		    None
+		},
+	    },
+	    Some(t) => {
+		self.error(&e.name, &format!("a value of type {t} has no properties"));
+		None
+	    },
+	}
+    }
+
+    fn visit_set(&mut self, e: &expr::Set) -> Option<ValType> {
+	match self.type_of(e.object.as_ref()) {
+	    Some(ValType::Object(_)) | None => {},
+	    Some(t) => self.error(&e.name, &format!("a value of type {t} has no fields")),
+	}
+	self.type_of(e.val.as_ref())
+    }
+
+    fn visit_self(&mut self, _e: &expr::SelfExpr) -> Option<ValType> {
+	self.in_class.last().map(|c| ValType::Object(c.clone()))
+    }
+
+    fn visit_super(&mut self, _e: &expr::Super) -> Option<ValType> {
+	Some(ValType::Fn)
+    }
+}
+
+impl StmtVisitor<()> for Checker {
+    fn visit_print(&mut self, s: &stmt::Print) {
+	let stmt::Print{expr: e, ..} = s;
+	self.type_of(e.as_ref());
+    }
+
+    fn visit_expression(&mut self, s: &stmt::Expression) {
+	let stmt::Expression{expr: e, ..} = s;
+	self.type_of(e.as_ref());
+    }
+
+    fn visit_typed_decl(&mut self, s: &stmt::TypedDecl) {
+	let stmt::TypedDecl{d_type: expect, name: n, init: e, ..} = s;
+	if let Some(e) = e {
+	    match self.type_of(e.as_ref()) {
+		Some(t) if t != *expect => {
+		    self.error(n, &format!("can't initialize {expect} '{}' with {t}", n.lexeme));
+		},
+		_ => {},
+	    }
+	}
+	self.define(n, Known::Var(expect.clone()));
+    }
+
+    fn visit_vardecl(&mut self, s: &stmt::VarDecl) {
+	let stmt::VarDecl{pattern: p, init: e, ..} = s;
+	let t = self.type_of(e.as_ref());
+	self.bind_pattern(p, t);
+    }
+
+    fn visit_decls(&mut self, s: &stmt::Decls) {
+	let stmt::Decls{decls: s, ..} = s;
+	for stmt in s.iter() {
+	    stmt.accept(self);
+	}
+    }
+
+    fn visit_fndecl(&mut self, s: &stmt::FnDecl) {
+	let stmt::FnDecl{name: n, init: e, ..} = s;
+	//defined first so a function can call itself
+	match e.as_ref() {
+	    Expr::Lambda(l) => self.define(n, Known::Func(l.def.clone())),
+	    _ => self.define(n, Known::Var(ValType::Fn)),
+	}
+	match self.type_of(e.as_ref()) {
+	    Some(ValType::Fn) | None => {},
+	    Some(t) => self.error(n, &format!("can't initialize fn '{}' with {t}", n.lexeme)),
+	}
+    }
+
+    fn visit_class(&mut self, s: &stmt::Class) {
+	let stmt::Class{name: n, superclass: sup, interfaces, methods, ..} = s;
+	let mut superclass = None;
+	if let Some(sup) = sup {
+	    match self.type_of(sup.as_ref()) {
+		Some(ValType::Class) | None => {},
+		Some(t) => self.error(n, &format!("superclass must be a class, not {t}")),
+	    }
+	    if let Expr::Variable(v) = sup.as_ref() {
+		superclass = Some(v.name.lexeme.clone());
+	    }
+	}
+	self.define(n, Known::Class(n.lexeme.clone()));
+	self.classes.insert(n.lexeme.clone(), ClassInfo {
+	    superclass,
+	    interfaces: interfaces.iter().map(|i| i.lexeme.clone()).collect(),
+	    methods: methods.clone(),
+	});
+	for i in interfaces.iter() {
+	    self.check_implements(n, i);
+	}
+	//the same scopes as the interpreter, super outside self outside the method
+	if sup.is_some() {
+	    self.scopes.push(Scope::new());
+	}
+	self.in_class.push(n.lexeme.clone());
+	for m in methods.iter() {
+	    self.scopes.push(Scope::new());
+	    self.function(m);
+	    self.scopes.pop();
+	}
+	self.in_class.pop();
+	if sup.is_some() {
+	    self.scopes.pop();
+	}
+    }
+
+    fn visit_interface(&mut self, s: &stmt::Interface) {
+	self.interfaces.insert(s.name.lexeme.clone(), s.methods.clone());
+    }
+
+    fn visit_block(&mut self, s: &stmt::Block) {
+	let stmt::Block{stmts: s, ..} = s;
+	self.scoped(s, None);
+    }
+
+    fn visit_if(&mut self, s: &stmt::If) {
+	let stmt::If{keyword: k, cond: c, then_branch: t, else_branch: e, ..} = s;
+	self.condition(k, c.as_ref(), "conditional expression must be boolean");
+	t.accept(self);
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+    }
+
+    fn visit_while(&mut self, s: &stmt::While) {
+	let stmt::While{keyword: k, cond: c, body: s, increment: i, ..} = s;
+	self.condition(k, c.as_ref(), "conditional expression must be boolean");
+	s.accept(self);
+	if let Some(i) = i {
+	    self.type_of(i.as_ref());
+	}
+    }
+
+    fn visit_forin(&mut self, s: &stmt::ForIn) {
+	let stmt::ForIn{name: n, iterable: e, body, ..} = s;
+	let item = match self.type_of(e.as_ref()) {
+	    Some(ValType::Range) => Known::Var(ValType::Int),
+	    Some(ValType::Str) => Known::Var(ValType::Char),
+	    Some(ValType::List(Some(t))) => Known::Var(*t),
+	    Some(ValType::List(None)) | Some(ValType::Tuple) | None => Known::Unknown,
+	    Some(t) => {
+		self.error(n, &format!("can't loop over a value of type {t}"));
+		Known::Unknown
+	    },
+	};
+	self.scoped(body, Some((n, item)));
+    }
+
+    fn visit_repeat(&mut self, s: &stmt::Repeat) {
+	let stmt::Repeat{keyword: k, body: s, cond: c, ..} = s;
+	s.accept(self);
+	self.condition(k, c.as_ref(), "conditional expression must be boolean");
+    }
+
+    fn visit_loop(&mut self, s: &stmt::Loop) {
+	let stmt::Loop{body: s, ..} = s;
+	s.accept(self)
+    }
+
+    fn visit_break(&mut self, _s: &stmt::Break) {}
+
+    fn visit_continue(&mut self, _s: &stmt::Continue) {}
+
+    fn visit_throw(&mut self, s: &stmt::Throw) {
+	let stmt::Throw{value: e, ..} = s;
+	self.type_of(e.as_ref());
+    }
+
+    fn visit_try(&mut self, s: &stmt::Try) {
+	let stmt::Try{body, name, handler, ..} = s;
+	self.scoped(body, None);
+	self.scoped(handler, Some((name, Known::Var(ValType::Err))));
+    }
+
+    fn visit_assert(&mut self, s: &stmt::Assert) {
+	let stmt::Assert{keyword: k, cond: c, message: m, ..} = s;
+	self.condition(k, c.as_ref(), "assertion must be boolean");
+	if let Some(m) = m {
+	    self.type_of(m.as_ref());
+	}
+    }
+
+    fn visit_return(&mut self, s: &stmt::Return) {
+	let t = match &s.value {
+	    Some(e) => self.type_of(e.as_ref()),
+	    None => Some(ValType::Nil),
+	};
+	self.check_return(t);
+    }
+}
+
+#[derive (Debug)]
+struct CheckError {}
+
+impl std::fmt::Display for CheckError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "type error")
+    }
+}
+
+impl Error for CheckError {}
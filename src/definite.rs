@@ -0,0 +1,479 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::rc::Rc;
+use crate::expr;
+use crate::expr::{Expr, ExprVisitor, FnDef, FnBody, Literal, Lit, NodeId};
+use crate::stmt;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::token::Token;
+use crate::resolver::Depths;
+
+//--strict: a variable declared without a value has to be assigned on
+//every path before anything reads it, instead of starting as 0, 0.0, ""
+//or '\0'. the scopes are the same as the resolver's, so a variable's
+//depth says which one it's in
+
+#[derive (Clone)]
+struct State {
+    //the variables declared without a value that are certainly assigned by now
+    assigned: HashSet<usize>,
+    //nothing gets here, it's after a return, break, continue or throw
+    dead: bool,
+}
+
+impl State {
+    //where two paths meet, only what both assigned is certain
+    fn join(a: State, b: State) -> State {
+	match (a.dead, b.dead) {
+	    (true, _) => b,
+	    (_, true) => a,
+	    _ => State {
+		assigned: a.assigned.intersection(&b.assigned).copied().collect(),
+		dead: false,
+	    },
+	}
+    }
+
+    fn dead() -> State {
+	State {
+	    assigned: HashSet::new(),
+	    dead: true,
+	}
+    }
+}
+
+//the states at each way out of a loop other than its condition
+struct Loop {
+    label: Option<Rc<String>>,
+    breaks: Vec<State>,
+    continues: Vec<State>,
+}
+
+pub struct Definite {
+    //name -> variable, only for the ones declared without a value
+    scopes: Vec<HashMap<Rc<String>, usize>>,
+    //each variable's declaration, a variable is its index in here
+    names: Vec<Token>,
+    state: State,
+    loops: Vec<Loop>,
+    //each variable is only reported once
+    reported: HashSet<usize>,
+    //what's known about the globals after the lines so far at the prompt
+    globals: HashMap<Rc<String>, usize>,
+    assigned: HashSet<usize>,
+    //where the resolver found each local variable
+    depths: Depths,
+    had_error: bool,
+}
+
+impl Definite {
+    pub fn new() -> Self {
+	Definite {
+	    scopes: Vec::new(),
+	    names: Vec::new(),
+	    state: State::dead(),
+	    loops: Vec::new(),
+	    reported: HashSet::new(),
+	    globals: HashMap::new(),
+	    assigned: HashSet::new(),
+	    depths: Depths::new(),
+	    had_error: false,
+	}
+    }
+
+    pub fn check(&mut self, ast: &[Stmt], depths: &Depths) -> Result<(), Box<dyn Error>> {
+	self.had_error = false;
+	self.depths = depths.clone();
+	self.scopes = vec![self.globals.clone()];
+	self.state = State {
+	    assigned: self.assigned.clone(),
+	    dead: false,
+	};
+	self.stmts(ast);
+	let globals = self.scopes.pop().expect("no globals, fix definite::check");
+	if self.had_error {
+	    return Err(Box::new(UnassignedError {}));
+	}
+	self.globals = globals;
+	self.assigned.extend(self.state.assigned.iter().copied());
+	Ok(())
+    }
+
+    fn stmts(&mut self, stmts: &[Stmt]) {
+	for stmt in stmts.iter() {
+	    stmt.accept(self);
+	}
+    }
+
+    fn scoped(&mut self, stmts: &[Stmt]) {
+	self.scopes.push(HashMap::new());
+	self.stmts(stmts);
+	self.scopes.pop();
+    }
+
+    fn lookup(&self, id: NodeId, name: &Token) -> Option<usize> {
+	let i = match self.depths.get(&id) {
+	    Some(d) => self.scopes.len().checked_sub(d.scopes + 1)?,
+	    None => 0,
+	};
+	self.scopes[i].get(&name.lexeme).copied()
+    }
+
+    fn read(&mut self, id: NodeId, name: &Token) {
+	let v = match self.lookup(id, name) {
+	    Some(v) => v,
+	    None => return,
+	};
+	if self.state.dead || self.state.assigned.contains(&v) || !self.reported.insert(v) {
+	    return;
+	}
+	crate::report(name.line, &format!(" at '{}'", name.lexeme),
+		      &format!("'{}' might be read before it's assigned, it's declared on line {} without a value",
+			       name.lexeme, self.names[v].line));
+	self.had_error = true;
+    }
+
+    fn assign(&mut self, v: Option<usize>) {
+	if let Some(v) = v {
+	    self.state.assigned.insert(v);
+	}
+    }
+
+    //a function can be called any time after it's made, so its body can
+    //only count on what's assigned by then, and what it assigns doesn't
+    //count outside since it might never be called
+    fn function(&mut self, def: &FnDef) {
+	let state = self.state.clone();
+	let loops = std::mem::take(&mut self.loops);
+	self.scopes.push(HashMap::new());
+	for p in def.params.iter() {
+	    if let Some(d) = &p.default {
+		d.accept(self);
+	    }
+	}
+	match &def.body {
+	    FnBody::Expr(b) => {
+		b.accept(self);
+	    },
+	    FnBody::Block(stmts) => self.stmts(stmts),
+	}
+	self.scopes.pop();
+	self.loops = loops;
+	self.state = state;
+    }
+
+    //the loop a break or continue leaves
+    fn target(&mut self, label: &Option<Token>) -> Option<&mut Loop> {
+	match label {
+	    Some(l) => self.loops.iter_mut().rev().find(|lp| lp.label.as_ref() == Some(&l.lexeme)),
+	    None => self.loops.last_mut(),
+	}
+    }
+
+    fn push_loop(&mut self, label: &Option<Token>) {
+	self.loops.push(Loop {
+	    label: label.as_ref().map(|l| l.lexeme.clone()),
+	    breaks: Vec::new(),
+	    continues: Vec::new(),
+	});
+    }
+
+    fn join_all(state: State, others: Vec<State>) -> State {
+	others.into_iter().fold(state, State::join)
+    }
+}
+
+//a condition that's the literal true, so the loop only ends with a break
+fn always(c: &Expr) -> bool {
+    matches!(c, Expr::Literal(Literal{value: Lit::BoolLit(true), ..}))
+}
+
+impl ExprVisitor<()> for Definite {
+    fn visit_binary(&mut self, e: &expr::Binary) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_unary(&mut self, e: &expr::Unary) {
+	e.right.accept(self)
+    }
+
+    fn visit_grouping(&mut self, e: &expr::Grouping) {
+	e.expression.accept(self)
+    }
+
+    fn visit_literal(&mut self, _e: &expr::Literal) {}
+
+    fn visit_assignment(&mut self, e: &expr::Assignment) {
+	e.val.accept(self);
+	let v = self.lookup(e.node.id, &e.name);
+	self.assign(v);
+    }
+
+    fn visit_variable(&mut self, e: &expr::Variable) {
+	self.read(e.node.id, &e.name);
+    }
+
+    //the right side might not run
+    fn visit_logical(&mut self, e: &expr::Logical) {
+	e.left.accept(self);
+	let state = self.state.clone();
+	e.right.accept(self);
+	self.state = State::join(state, self.state.clone());
+    }
+
+    fn visit_cast(&mut self, e: &expr::Cast) {
+	e.expr.accept(self)
+    }
+
+    fn visit_conditional(&mut self, e: &expr::Conditional) {
+	e.cond.accept(self);
+	let state = self.state.clone();
+	e.then_expr.accept(self);
+	let then_state = std::mem::replace(&mut self.state, state);
+	e.else_expr.accept(self);
+	self.state = State::join(then_state, self.state.clone());
+    }
+
+    fn visit_lambda(&mut self, e: &expr::Lambda) {
+	self.function(&e.def);
+    }
+
+    fn visit_call(&mut self, e: &expr::Call) {
+	e.callee.accept(self);
+	for arg in e.args.iter() {
+	    arg.value.accept(self);
+	}
+    }
+
+    fn visit_index(&mut self, e: &expr::Index) {
+	e.object.accept(self);
+	e.index.accept(self)
+    }
+
+    fn visit_range(&mut self, e: &expr::Range) {
+	e.start.accept(self);
+	e.end.accept(self);
+	if let Some(s) = &e.step {
+	    s.accept(self);
+	}
+    }
+
+    fn visit_tuple(&mut self, e: &expr::Tuple) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_list(&mut self, e: &expr::List) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    //the names aren't resolved, so they're found by name from the innermost scope
+    fn visit_destructure(&mut self, e: &expr::Destructure) {
+	e.val.accept(self);
+	for n in e.target.names() {
+	    let v = self.scopes.iter().rev().find_map(|s| s.get(&n.lexeme).copied());
+	    self.assign(v);
+	}
+    }
+
+    fn visit_get(&mut self, e: &expr::Get) {
+	e.object.accept(self)
+    }
+
+    fn visit_set(&mut self, e: &expr::Set) {
+	e.object.accept(self);
+	e.val.accept(self)
+    }
+
+    fn visit_self(&mut self, _e: &expr::SelfExpr) {}
+
+    fn visit_super(&mut self, _e: &expr::Super) {}
+}
+
+impl StmtVisitor<()> for Definite {
+    fn visit_print(&mut self, s: &stmt::Print) {
+	let stmt::Print{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_expression(&mut self, s: &stmt::Expression) {
+	let stmt::Expression{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_typed_decl(&mut self, s: &stmt::TypedDecl) {
+	let stmt::TypedDecl{name: n, init: e, ..} = s;
+	match e {
+	    Some(e) => {
+		e.accept(self);
+	    },
+	    None => {
+		self.scopes.last_mut().expect("no scope, fix definite::visit_typed_decl")
+		    .insert(n.lexeme.clone(), self.names.len());
+		self.names.push(n.clone());
+	    },
+	}
+    }
+
+    fn visit_vardecl(&mut self, s: &stmt::VarDecl) {
+	let stmt::VarDecl{init: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_decls(&mut self, s: &stmt::Decls) {
+	let stmt::Decls{decls: s, ..} = s;
+	self.stmts(s);
+    }
+
+    fn visit_fndecl(&mut self, s: &stmt::FnDecl) {
+	let stmt::FnDecl{init: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_class(&mut self, s: &stmt::Class) {
+	let stmt::Class{superclass: sup, methods, ..} = s;
+	if let Some(sup) = sup {
+	    sup.accept(self);
+	    self.scopes.push(HashMap::new());
+	}
+	for m in methods.iter() {
+	    //self
+	    self.scopes.push(HashMap::new());
+	    self.function(m);
+	    self.scopes.pop();
+	}
+	if sup.is_some() {
+	    self.scopes.pop();
+	}
+    }
+
+    fn visit_interface(&mut self, _s: &stmt::Interface) {}
+
+    fn visit_block(&mut self, s: &stmt::Block) {
+	let stmt::Block{stmts: s, ..} = s;
+	self.scoped(s);
+    }
+
+    fn visit_if(&mut self, s: &stmt::If) {
+	let stmt::If{cond: c, then_branch: t, else_branch: e, ..} = s;
+	c.accept(self);
+	let state = self.state.clone();
+	t.accept(self);
+	let then_state = std::mem::replace(&mut self.state, state);
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+	self.state = State::join(then_state, self.state.clone());
+    }
+
+    //the body might not run at all, unless the condition is true,
+    //and then the only way out is a break
+    fn visit_while(&mut self, s: &stmt::While) {
+	let stmt::While{label: l, cond: c, body: s, increment: i, ..} = s;
+	c.accept(self);
+	let after = match always(c.as_ref()) {
+	    true => State::dead(),
+	    false => self.state.clone(),
+	};
+	self.push_loop(l);
+	s.accept(self);
+	let lp = self.loops.pop().expect("no loop, fix definite::visit_while");
+	if let Some(i) = i {
+	    self.state = Definite::join_all(self.state.clone(), lp.continues);
+	    i.accept(self);
+	}
+	self.state = Definite::join_all(after, lp.breaks);
+    }
+
+    fn visit_forin(&mut self, s: &stmt::ForIn) {
+	let stmt::ForIn{label: l, iterable: e, body, ..} = s;
+	e.accept(self);
+	let after = self.state.clone();
+	self.push_loop(l);
+	self.scoped(body);
+	let lp = self.loops.pop().expect("no loop, fix definite::visit_forin");
+	self.state = Definite::join_all(after, lp.breaks);
+    }
+
+    //the body always runs once, and continue goes to the condition
+    fn visit_repeat(&mut self, s: &stmt::Repeat) {
+	let stmt::Repeat{label: l, body: s, cond: c, ..} = s;
+	self.push_loop(l);
+	s.accept(self);
+	let lp = self.loops.pop().expect("no loop, fix definite::visit_repeat");
+	self.state = Definite::join_all(self.state.clone(), lp.continues);
+	c.accept(self);
+	self.state = Definite::join_all(self.state.clone(), lp.breaks);
+    }
+
+    fn visit_loop(&mut self, s: &stmt::Loop) {
+	let stmt::Loop{label: l, body: s, ..} = s;
+	self.push_loop(l);
+	s.accept(self);
+	let lp = self.loops.pop().expect("no loop, fix definite::visit_loop");
+	self.state = Definite::join_all(State::dead(), lp.breaks);
+    }
+
+    fn visit_break(&mut self, s: &stmt::Break) {
+	let stmt::Break{label: l, ..} = s;
+	let state = std::mem::replace(&mut self.state, State::dead());
+	if let Some(lp) = self.target(l) {
+	    lp.breaks.push(state);
+	}
+    }
+
+    fn visit_continue(&mut self, s: &stmt::Continue) {
+	let stmt::Continue{label: l, ..} = s;
+	let state = std::mem::replace(&mut self.state, State::dead());
+	if let Some(lp) = self.target(l) {
+	    lp.continues.push(state);
+	}
+    }
+
+    fn visit_throw(&mut self, s: &stmt::Throw) {
+	let stmt::Throw{value: e, ..} = s;
+	e.accept(self);
+	self.state = State::dead();
+    }
+
+    //the body can fail anywhere, so the handler can't count on anything it assigns
+    fn visit_try(&mut self, s: &stmt::Try) {
+	let stmt::Try{body, handler, ..} = s;
+	let state = self.state.clone();
+	self.scoped(body);
+	let body_state = std::mem::replace(&mut self.state, state);
+	self.scoped(handler);
+	self.state = State::join(body_state, self.state.clone());
+    }
+
+    fn visit_assert(&mut self, s: &stmt::Assert) {
+	let stmt::Assert{cond: c, message: m, ..} = s;
+	c.accept(self);
+	if let Some(m) = m {
+	    m.accept(self);
+	}
+    }
+
+    fn visit_return(&mut self, s: &stmt::Return) {
+	let stmt::Return{value: e, ..} = s;
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+	self.state = State::dead();
+    }
+}
+
+#[derive (Debug)]
+struct UnassignedError {}
+
+impl std::fmt::Display for UnassignedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "unassigned variable")
+    }
+}
+
+impl Error for UnassignedError {}
@@ -3,17 +3,39 @@ use std::error::Error;
 use std::rc::Rc;
 use std::cell::RefCell;
 use crate::expr;
+use crate::token::Token;
+use crate::{ErrorKind, RuntimeError};
 
 pub struct Environment {
     values: HashMap<Rc<String>, expr::Value>,
+    //var parameters, names that stand for a variable in another environment
+    refs: HashMap<Rc<String>, Place>,
+    //variables declared with a type a value only has to fit, like a
+    //superclass, the rest keep the type of the value they started with
+    types: HashMap<Rc<String>, expr::ValType>,
     parent: Option<Rc<RefCell<Environment>>>,
 }
 
+//where a variable actually lives
+#[derive (Clone)]
+pub struct Place {
+    pub env: Rc<RefCell<Environment>>,
+    pub name: Token,
+}
+
+impl std::fmt::Debug for Place {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "<var {}>", self.name.lexeme)
+    }
+}
+
 impl Environment {
     pub fn new(parent: Option<Rc<RefCell<Environment>>>) -> Self {
 	Environment {
 	    values: HashMap::new(),
-	    parent: parent,
+	    refs: HashMap::new(),
+	    types: HashMap::new(),
+	    parent,
 	}
     }
 
@@ -21,31 +43,85 @@ impl Environment {
 	self.values.insert(name.clone(), value);
     }
 
-    pub fn get(&self, name: &Rc<String>) -> Result<expr::Value, Box<dyn Error>> {
-	match self.values.get(name) {
+    pub fn define_typed(&mut self, name: &Rc<String>, value: expr::Value, t: expr::ValType) {
+	self.types.insert(name.clone(), t);
+	self.values.insert(name.clone(), value);
+    }
+
+    pub fn define_ref(&mut self, name: &Rc<String>, place: Place) {
+	self.refs.insert(name.clone(), place);
+    }
+
+    //find the variable name refers to from env, following var parameters
+    //so a place never points at another reference
+    pub fn locate(env: &Rc<RefCell<Environment>>, name: &Token) -> Option<Place> {
+	let e = env.borrow();
+	if e.values.contains_key(&name.lexeme) {
+	    return Some(Place { env: env.clone(), name: name.clone() });
+	}
+	if let Some(p) = e.refs.get(&name.lexeme) {
+	    return Some(p.clone());
+	}
+	match &e.parent {
+	    Some(p) => Environment::locate(p, name),
+	    None => None,
+	}
+    }
+
+    //the environment depth scopes out from env, where the resolver found a variable
+    pub fn ancestor(env: &Rc<RefCell<Environment>>, depth: usize) -> Rc<RefCell<Environment>> {
+	let mut env = env.clone();
+	for _ in 0..depth {
+	    let parent = env.borrow().parent.clone().expect("ran out of scopes, fix the resolver");
+	    env = parent;
+	}
+	env
+    }
+
+    pub fn get(&self, name: &Token) -> Result<expr::Value, Box<dyn Error>> {
+	if let Some(p) = self.refs.get(&name.lexeme) {
+	    return p.env.borrow().get(&p.name);
+	}
+	match self.values.get(&name.lexeme) {
 	    Some(v) => Ok(v.clone()),
 	    None => {
 		match &self.parent {
 		    Some(p) => p.borrow().get(name),
 		    None => {
-			println!("undefined variable {}", name.clone());
-			Err(Box::new(crate::RuntimeError {}))
+			Err(Box::new(RuntimeError::new(ErrorKind::Undefined,
+						       format!("undefined variable {}", name.lexeme),
+						       name.line)))
 		    }
 		}
 	    },
 	}
     }
 
-    pub fn assign(&mut self, name: &Rc<String>, value: &expr::Value) -> Result<(), Box<dyn Error>> {
-	if self.values.contains_key(name) {
-	    self.values.insert(name.clone(), value.clone());
+    //the type a value assigned to name has to be
+    pub fn declared(&self, name: &Token) -> Result<expr::ValType, Box<dyn Error>> {
+	if let Some(p) = self.refs.get(&name.lexeme) {
+	    return p.env.borrow().declared(&p.name);
+	}
+	match self.types.get(&name.lexeme) {
+	    Some(t) => Ok(t.clone()),
+	    None => Ok(self.get(name)?.val_type()),
+	}
+    }
+
+    pub fn assign(&mut self, name: &Token, value: &expr::Value) -> Result<(), Box<dyn Error>> {
+	if let Some(p) = self.refs.get(&name.lexeme) {
+	    return p.env.borrow_mut().assign(&p.name, value);
+	}
+	if self.values.contains_key(&name.lexeme) {
+	    self.values.insert(name.lexeme.clone(), value.clone());
 	    Ok(())
 	} else {
 	    match &self.parent {
 		Some(p) => p.borrow_mut().assign(name, value),
 		None => {
-		    println!("undefined variable {}", name);
-		    Err(Box::new(crate::RuntimeError {}))
+		    Err(Box::new(RuntimeError::new(ErrorKind::Undefined,
+						   format!("undefined variable {}", name.lexeme),
+						   name.line)))
 		},
 	    }
 	}
@@ -1,286 +1,1088 @@
-use std::error::Error;
-use std::any::Any;
 use std::rc::Rc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fmt;
-use crate::token::Token;
+use crate::token::{Token, Span};
+use crate::stmt::Stmt;
+use crate::environment::Environment;
+use crate::RuntimeError;
 
+//the Val suffix keeps values apart from the ValType variants of the same name
+#[allow(clippy::enum_variant_names)]
 #[derive (Debug, PartialEq, Clone)]
 pub enum Value {
     RealVal(f32),
     IntVal(u32),
     StrVal(Rc<String>),
+    CharVal(char),
     BoolVal(bool),
+    ErrVal(Rc<RuntimeError>),
+    FnVal(Rc<Function>),
+    ListVal(Rc<Vec<Value>>),
+    RangeVal(IntRange),
+    TupleVal(Rc<Vec<Value>>),
+    ClassVal(Rc<Class>),
+    InstanceVal(Rc<RefCell<Instance>>),
     NilVal,
 }
 
+#[derive (Debug, PartialEq, Clone, Copy)]
+pub struct IntRange {
+    pub start: u32,
+    pub end: u32,
+    pub step: u32,
+    pub inclusive: bool,
+}
+
+impl IntRange {
+    pub fn iter(&self) -> impl Iterator<Item = u32> + use<> {
+	//u64 so that an inclusive range can end at u32::MAX
+	let end = self.end as u64 + self.inclusive as u64;
+	(self.start as u64..end).step_by(self.step as usize).map(|i| i as u32)
+    }
+}
+
+impl fmt::Display for IntRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "{}{}{}", self.start, if self.inclusive { "..=" } else { ".." }, self.end)?;
+	if self.step != 1 {
+	    write!(f, " step {}", self.step)?;
+	}
+	Ok(())
+    }
+}
+
+impl Value {
+    pub fn val_type(&self) -> ValType {
+	match self {
+	    Value::RealVal(_) => ValType::Real,
+	    Value::IntVal(_) => ValType::Int,
+	    Value::StrVal(_) => ValType::Str,
+	    Value::CharVal(_) => ValType::Char,
+	    Value::BoolVal(_) => ValType::Bool,
+	    Value::ErrVal(_) => ValType::Err,
+	    Value::FnVal(_) => ValType::Fn,
+	    Value::ListVal(_) => ValType::List(None),
+	    Value::RangeVal(_) => ValType::Range,
+	    Value::TupleVal(_) => ValType::Tuple,
+	    Value::ClassVal(_) => ValType::Class,
+	    Value::InstanceVal(i) => ValType::Object(i.borrow().class.name.lexeme.clone()),
+	    Value::NilVal => ValType::Nil,
+	}
+    }
+
+    //whether a value can go where a t is declared, the same as having
+    //that type except that an instance of a subclass is also its superclass,
+    //and an instance of a class is also any interface the class implements
+    pub fn is(&self, t: &ValType) -> bool {
+	match (self, t) {
+	    (Value::InstanceVal(i), ValType::Object(n)) => i.borrow().class.is_a(n),
+	    (Value::ListVal(l), ValType::List(Some(t))) => l.iter().all(|v| v.is(t)),
+	    //a type parameter nothing has decided yet could be anything
+	    (_, ValType::Param(_)) => true,
+	    _ => self.val_type() == *t,
+	}
+    }
+
+    //the iteration protocol, anything that gives an iterator here
+    //can be looped over with for x in ...
+    pub fn iter(&self) -> Option<Box<dyn Iterator<Item = Value>>> {
+	match self {
+	    Value::RangeVal(r) => Some(Box::new(r.iter().map(Value::IntVal))),
+	    Value::StrVal(s) => {
+		let chars: Vec<char> = s.chars().collect();
+		Some(Box::new(chars.into_iter().map(Value::CharVal)))
+	    },
+	    Value::ListVal(l) | Value::TupleVal(l) => {
+		let l = l.clone();
+		Some(Box::new((0..l.len()).map(move |i| l[i].clone())))
+	    },
+	    _ => None,
+	}
+    }
+}
+
 impl fmt::Display for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 	match self {
 	    Value::RealVal(r) => write!(f, "{r}"),
 	    Value::IntVal(i) => write!(f, "{i}"),
 	    Value::StrVal(s) => write!(f, "{s}"),
+	    Value::CharVal(c) => write!(f, "{c}"),
+	    Value::ErrVal(e) => write!(f, "{e}"),
+	    Value::FnVal(func) => write!(f, "{:?}", func),
+	    Value::ListVal(l) => {
+		let items: Vec<String> = l.iter().map(|v| format!("{v}")).collect();
+		write!(f, "[{}]", items.join(", "))
+	    },
+	    Value::RangeVal(r) => write!(f, "{r}"),
+	    Value::TupleVal(t) => {
+		let items: Vec<String> = t.iter().map(|v| format!("{v}")).collect();
+		write!(f, "({})", items.join(", "))
+	    },
+	    Value::ClassVal(c) => write!(f, "<class {}>", c.name.lexeme),
+	    Value::InstanceVal(i) => write!(f, "<{} instance>", i.borrow().class.name.lexeme),
 	    Value::BoolVal(b) => write!(f, "{b}"),
 	    Value::NilVal => write!(f, "nil"),
 	}
     }
 }
 
-//so far only need this in the assignment parsing,
-//feels like it could be refactored out
-pub enum ExprType {
-    Binary,
-    Unary,
-    Grouping,
-    Literal,
-    Assignment,
-    Variable,
-    Logical,
+//the types a parameter or return value can be declared as
+#[derive (Debug, PartialEq, Clone)]
+pub enum ValType {
+    Real,
+    Int,
+    Str,
+    Char,
+    Bool,
+    Err,
+    Fn,
+    //list<int>, a bare list can hold anything
+    List(Option<Box<ValType>>),
+    Range,
+    Tuple,
+    Class,
+    //an instance of the named class
+    Object(Rc<String>),
+    //a generic function's type parameter, T in max<T>
+    Param(Rc<String>),
+    Nil,
 }
 
-pub trait Expr {
-    fn print(&self) -> String;
-    fn kind(&self) -> ExprType;
-    //another hack only needed for assignment
-    fn as_any(&self) -> &dyn Any;
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>>;
+//what each type parameter stands for in one call of a generic function
+pub type Types = HashMap<Rc<String>, ValType>;
+
+impl ValType {
+    //the type with its type parameter replaced by what it stands for, if it has one
+    pub fn instantiate(&self, types: &Types) -> ValType {
+	match self {
+	    ValType::Param(n) => types.get(n).cloned().unwrap_or(self.clone()),
+	    ValType::List(Some(t)) => ValType::List(Some(Box::new(t.instantiate(types)))),
+	    t => t.clone(),
+	}
+    }
+
+    //there's nowhere for a type to have more than one
+    pub fn type_param(&self) -> Option<&Rc<String>> {
+	match self {
+	    ValType::Param(n) => Some(n),
+	    ValType::List(Some(t)) => t.type_param(),
+	    _ => None,
+	}
+    }
 }
 
-pub trait ExprVisitor {
-    fn visit_binary(&mut self, e: &Binary) -> Result<Value, Box<dyn Error>>;
-    fn visit_unary(&mut self, e: &Unary) -> Result<Value, Box<dyn Error>>;
-    fn visit_grouping(&mut self, e: &Grouping) -> Result<Value, Box<dyn Error>>;
-    fn visit_literal(&mut self, e: &Literal) -> Result<Value, Box<dyn Error>>;
-    fn visit_assignment(&mut self, e: &Assignment) -> Result<Value, Box<dyn Error>>;
-    fn visit_variable(&mut self, e: &Variable) -> Result<Value, Box<dyn Error>>;
-    fn visit_logical(&mut self, e: &Logical) -> Result<Value, Box<dyn Error>>;
+impl fmt::Display for ValType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match self {
+	    ValType::Real => write!(f, "real"),
+	    ValType::Int => write!(f, "int"),
+	    ValType::Str => write!(f, "str"),
+	    ValType::Char => write!(f, "char"),
+	    ValType::Bool => write!(f, "bool"),
+	    ValType::Err => write!(f, "error"),
+	    ValType::Fn => write!(f, "fn"),
+	    ValType::List(None) => write!(f, "list"),
+	    ValType::List(Some(t)) => write!(f, "list<{t}>"),
+	    ValType::Range => write!(f, "range"),
+	    ValType::Tuple => write!(f, "tuple"),
+	    ValType::Class => write!(f, "class"),
+	    ValType::Object(n) | ValType::Param(n) => write!(f, "{n}"),
+	    ValType::Nil => write!(f, "nil"),
+	}
+    }
 }
 
-pub struct Binary {
-    pub left: Box<dyn Expr>,
-    pub operator: Token,
-    pub right: Box<dyn Expr>,
+//a function value, the definition plus its static links: the scope
+//it was made in, then the scope each function around that was made in,
+//as far out as the body reaches for variables
+pub struct Function {
+    pub def: Rc<FnDef>,
+    pub links: Vec<Rc<RefCell<Environment>>>,
 }
 
-impl Binary {
-    pub fn new(left: Box<dyn Expr>,
-	       operator: Token,
-	       right: Box<dyn Expr>) -> Self {
-	Binary {
-	    left: left,
-	    operator: operator,
-	    right: right,
+impl PartialEq for Function {
+    fn eq(&self, other: &Self) -> bool {
+	Rc::ptr_eq(&self.def, &other.def) && self.links.len() == other.links.len() &&
+	    self.links.iter().zip(other.links.iter()).all(|(a, b)| Rc::ptr_eq(a, b))
+    }
+}
+
+impl fmt::Debug for Function {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	match &self.def.name {
+	    Some(n) => write!(f, "<fn {}>", n.lexeme),
+	    None => write!(f, "<fn>"),
 	}
     }
 }
 
-impl Expr for Binary {
-    fn print(&self) -> String {
-	format!("({} {} {})", self.operator.lexeme,
-		self.left.print(), self.right.print())
+pub struct Class {
+    pub name: Token,
+    pub superclass: Option<Rc<Class>>,
+    pub methods: HashMap<String, Rc<Function>>,
+    //the interfaces it says it implements, checked by the parser
+    pub interfaces: Vec<Rc<String>>,
+}
+
+impl Class {
+    //looks in the superclasses too
+    pub fn find_method(&self, name: &str) -> Option<Rc<Function>> {
+	match self.methods.get(name) {
+	    Some(m) => Some(m.clone()),
+	    None => self.superclass.as_ref().and_then(|s| s.find_method(name)),
+	}
     }
 
-    fn as_any(&self) -> &dyn Any {
-	self
+    //name is this class, a superclass or an interface of either
+    pub fn is_a(&self, name: &str) -> bool {
+	*self.name.lexeme == name || self.interfaces.iter().any(|i| **i == name) ||
+	    self.superclass.as_ref().is_some_and(|s| s.is_a(name))
     }
+}
 
-    fn kind(&self) -> ExprType {
-	ExprType::Binary
+impl PartialEq for Class {
+    fn eq(&self, other: &Self) -> bool {
+	std::ptr::eq(self, other)
     }
+}
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_binary(&self)
+impl fmt::Debug for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "<class {}>", self.name.lexeme)
     }
 }
 
-pub struct Grouping {
-    pub expression: Box<dyn Expr>,
+pub struct Instance {
+    pub class: Rc<Class>,
+    pub fields: HashMap<Rc<String>, Value>,
 }
 
-impl Grouping {
-    pub fn new(expression: Box<dyn Expr>) -> Self {
-	Grouping {
-	    expression: expression,
+impl Instance {
+    pub fn new(class: Rc<Class>) -> Self {
+	Instance {
+	    class,
+	    fields: HashMap::new(),
 	}
     }
 }
 
-impl Expr for Grouping {
-    fn print(&self) -> String {
-	format!("(group {})", self.expression.print())
+//two instances are only equal if they're the same one
+impl PartialEq for Instance {
+    fn eq(&self, other: &Self) -> bool {
+	std::ptr::eq(self, other)
     }
+}
 
-    fn kind(&self) -> ExprType {
-	ExprType::Grouping
+impl fmt::Debug for Instance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+	write!(f, "<{} instance>", self.class.name.lexeme)
     }
+}
+
+//tells nodes apart, even ones with the same span (e.g. from a desugared for),
+//so a pass can keep what it finds out about a node in a map
+#[derive (Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeId(pub u32);
+
+//ids carry on from one line to the next at the prompt, so they're never reused
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
 
-    fn as_any(&self) -> &dyn Any {
-	self
+impl NodeId {
+    pub fn next() -> Self {
+	NodeId(NEXT_ID.fetch_add(1, Ordering::Relaxed))
     }
+}
+
+//what every node in the tree carries
+#[derive (Debug, Clone, Copy)]
+pub struct Node {
+    pub id: NodeId,
+    pub span: Span,
+}
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_grouping(&self)
+impl Node {
+    pub fn new(span: Span) -> Self {
+	Node {
+	    id: NodeId::next(),
+	    span,
+	}
     }
 }
 
-#[derive (Debug)]
-pub enum Literal {
-    BoolLit(bool),
-    StrLit(Rc<String>),
-    RealLit(f32),
-    IntLit(u32),
-    NilLit,
+#[derive (Clone)]
+pub enum Expr {
+    Binary(Binary),
+    Unary(Unary),
+    Grouping(Grouping),
+    Literal(Literal),
+    Assignment(Assignment),
+    Variable(Variable),
+    Logical(Logical),
+    Cast(Cast),
+    Conditional(Conditional),
+    Lambda(Lambda),
+    Call(Call),
+    Index(Index),
+    Range(Range),
+    Tuple(Tuple),
+    List(List),
+    Destructure(Destructure),
+    Get(Get),
+    Set(Set),
+    SelfExpr(SelfExpr),
+    Super(Super),
 }
 
-impl Expr for Literal {
-    fn print(&self) -> String {
+impl Expr {
+    pub fn node(&self) -> &Node {
+	match self {
+	    Expr::Binary(e) => &e.node,
+	    Expr::Unary(e) => &e.node,
+	    Expr::Grouping(e) => &e.node,
+	    Expr::Literal(e) => &e.node,
+	    Expr::Assignment(e) => &e.node,
+	    Expr::Variable(e) => &e.node,
+	    Expr::Logical(e) => &e.node,
+	    Expr::Cast(e) => &e.node,
+	    Expr::Conditional(e) => &e.node,
+	    Expr::Lambda(e) => &e.node,
+	    Expr::Call(e) => &e.node,
+	    Expr::Index(e) => &e.node,
+	    Expr::Range(e) => &e.node,
+	    Expr::Tuple(e) => &e.node,
+	    Expr::List(e) => &e.node,
+	    Expr::Destructure(e) => &e.node,
+	    Expr::Get(e) => &e.node,
+	    Expr::Set(e) => &e.node,
+	    Expr::SelfExpr(e) => &e.node,
+	    Expr::Super(e) => &e.node,
+	}
+    }
+
+    pub fn span(&self) -> Span {
+	self.node().span
+    }
+
+    pub fn id(&self) -> NodeId {
+	self.node().id
+    }
+
+    pub fn print(&self) -> String {
+	match self {
+	    Expr::Binary(e) => e.print(),
+	    Expr::Unary(e) => e.print(),
+	    Expr::Grouping(e) => e.print(),
+	    Expr::Literal(e) => e.print(),
+	    Expr::Assignment(e) => e.print(),
+	    Expr::Variable(e) => e.print(),
+	    Expr::Logical(e) => e.print(),
+	    Expr::Cast(e) => e.print(),
+	    Expr::Conditional(e) => e.print(),
+	    Expr::Lambda(e) => e.print(),
+	    Expr::Call(e) => e.print(),
+	    Expr::Index(e) => e.print(),
+	    Expr::Range(e) => e.print(),
+	    Expr::Tuple(e) => e.print(),
+	    Expr::List(e) => e.print(),
+	    Expr::Destructure(e) => e.print(),
+	    Expr::Get(e) => e.print(),
+	    Expr::Set(e) => e.print(),
+	    Expr::SelfExpr(e) => e.print(),
+	    Expr::Super(e) => e.print(),
+	}
+    }
+
+    pub fn accept<T, V: ExprVisitor<T> + ?Sized>(&self, visitor: &mut V) -> T {
 	match self {
-	    Self::NilLit => format!("nil"),
-	    Self::StrLit(s) => format!("{s}"),
-	    Self::RealLit(r) => format!("{r}"),
-	    Self::IntLit(i) => format!("{i}"),
-	    Self::BoolLit(b) => format!("{b}"),
+	    Expr::Binary(e) => visitor.visit_binary(e),
+	    Expr::Unary(e) => visitor.visit_unary(e),
+	    Expr::Grouping(e) => visitor.visit_grouping(e),
+	    Expr::Literal(e) => visitor.visit_literal(e),
+	    Expr::Assignment(e) => visitor.visit_assignment(e),
+	    Expr::Variable(e) => visitor.visit_variable(e),
+	    Expr::Logical(e) => visitor.visit_logical(e),
+	    Expr::Cast(e) => visitor.visit_cast(e),
+	    Expr::Conditional(e) => visitor.visit_conditional(e),
+	    Expr::Lambda(e) => visitor.visit_lambda(e),
+	    Expr::Call(e) => visitor.visit_call(e),
+	    Expr::Index(e) => visitor.visit_index(e),
+	    Expr::Range(e) => visitor.visit_range(e),
+	    Expr::Tuple(e) => visitor.visit_tuple(e),
+	    Expr::List(e) => visitor.visit_list(e),
+	    Expr::Destructure(e) => visitor.visit_destructure(e),
+	    Expr::Get(e) => visitor.visit_get(e),
+	    Expr::Set(e) => visitor.visit_set(e),
+	    Expr::SelfExpr(e) => visitor.visit_self(e),
+	    Expr::Super(e) => visitor.visit_super(e),
+	}
+    }
+}
+
+//T is whatever the walk works out for each node, e.g. the interpreter's
+//Result<Value, ...> or the checker's type
+pub trait ExprVisitor<T> {
+    fn visit_binary(&mut self, e: &Binary) -> T;
+    fn visit_unary(&mut self, e: &Unary) -> T;
+    fn visit_grouping(&mut self, e: &Grouping) -> T;
+    fn visit_literal(&mut self, e: &Literal) -> T;
+    fn visit_assignment(&mut self, e: &Assignment) -> T;
+    fn visit_variable(&mut self, e: &Variable) -> T;
+    fn visit_logical(&mut self, e: &Logical) -> T;
+    fn visit_cast(&mut self, e: &Cast) -> T;
+    fn visit_conditional(&mut self, e: &Conditional) -> T;
+    fn visit_lambda(&mut self, e: &Lambda) -> T;
+    fn visit_call(&mut self, e: &Call) -> T;
+    fn visit_index(&mut self, e: &Index) -> T;
+    fn visit_range(&mut self, e: &Range) -> T;
+    fn visit_tuple(&mut self, e: &Tuple) -> T;
+    fn visit_list(&mut self, e: &List) -> T;
+    fn visit_destructure(&mut self, e: &Destructure) -> T;
+    fn visit_get(&mut self, e: &Get) -> T;
+    fn visit_set(&mut self, e: &Set) -> T;
+    fn visit_self(&mut self, e: &SelfExpr) -> T;
+    fn visit_super(&mut self, e: &Super) -> T;
+}
+
+#[derive (Clone)]
+pub struct Binary {
+    pub node: Node,
+    pub left: Box<Expr>,
+    pub operator: Token,
+    pub right: Box<Expr>,
+}
+
+impl Binary {
+    pub fn new(left: Box<Expr>,
+	       operator: Token,
+	       right: Box<Expr>) -> Self {
+	Binary {
+	    node: Node::new(left.span().to(right.span())),
+	    left,
+	    operator,
+	    right,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("({} {} {})", self.operator.lexeme,
+		self.left.print(), self.right.print())
+    }
+}
+
+#[derive (Clone)]
+pub struct Grouping {
+    pub node: Node,
+    pub expression: Box<Expr>,
+}
+
+impl Grouping {
+    //the span takes in the parentheses
+    pub fn new(span: Span, expression: Box<Expr>) -> Self {
+	Grouping {
+	    node: Node::new(span),
+	    expression,
 	}
     }
 
-    fn kind(&self) -> ExprType {
-	ExprType::Literal
+    pub fn print(&self) -> String {
+	format!("(group {})", self.expression.print())
     }
+}
+
+//the Lit suffix matches the literal tokens they come from
+#[allow(clippy::enum_variant_names)]
+#[derive (Debug, Clone)]
+pub enum Lit {
+    BoolLit(bool),
+    StrLit(Rc<String>),
+    RealLit(f32),
+    IntLit(u32),
+    CharLit(char),
+    NilLit,
+}
 
-    fn as_any(&self) -> &dyn Any {
-	self
+#[derive (Clone)]
+pub struct Literal {
+    pub node: Node,
+    pub value: Lit,
+}
+
+impl Literal {
+    pub fn new(span: Span, value: Lit) -> Self {
+	Literal {
+	    node: Node::new(span),
+	    value,
+	}
     }
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_literal(&self)
+    pub fn print(&self) -> String {
+	match &self.value {
+	    Lit::NilLit => "nil".to_string(),
+	    Lit::StrLit(s) => format!("{s}"),
+	    Lit::RealLit(r) => format!("{r}"),
+	    Lit::IntLit(i) => format!("{i}"),
+	    Lit::CharLit(c) => format!("'{}'", c.escape_default()),
+	    Lit::BoolLit(b) => format!("{b}"),
+	}
     }
 }
 
+#[derive (Clone)]
 pub struct Unary {
+    pub node: Node,
     pub operator: Token,
-    pub right: Box<dyn Expr>,
+    pub right: Box<Expr>,
 }
 
 impl Unary {
     pub fn new(operator: Token,
-	       right: Box<dyn Expr>) -> Self {
+	       right: Box<Expr>) -> Self {
 	Unary {
-	    operator: operator,
-	    right: right,
+	    node: Node::new(operator.span().to(right.span())),
+	    operator,
+	    right,
 	}
     }
-}
 
-impl Expr for Unary {
-    fn print(&self) -> String {
+    pub fn print(&self) -> String {
 	format!("({} {})", self.operator.lexeme,
 		self.right.print())
     }
+}
+
+#[derive (Clone)]
+pub struct Assignment {
+    pub node: Node,
+    pub name: Token,
+    pub val: Box<Expr>,
+}
+
+impl Assignment {
+    pub fn new(name: Token, val: Box<Expr>) -> Self {
+	Assignment {
+	    node: Node::new(name.span().to(val.span())),
+	    name,
+	    val,
+	}
+    }
 
-    fn kind(&self) -> ExprType {
-	ExprType::Unary
+    pub fn print(&self) -> String {
+	format!("(= {} {})", self.name.lexeme, self.val.print())
     }
+}
 
-    fn as_any(&self) -> &dyn Any {
-	self
+#[derive (Clone)]
+pub struct Variable {
+    pub node: Node,
+    pub name: Token,
+}
+
+impl Variable {
+    pub fn new(name: Token) -> Self {
+	Variable {
+	    node: Node::new(name.span()),
+	    name,
+	}
     }
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_unary(&self)
+    pub fn print(&self) -> String {
+	format!("{}", self.name.lexeme)
     }
 }
 
-pub struct Assignment {
-    pub name: Rc<String>,
-    pub val: Box<dyn Expr>,
+#[derive (Clone)]
+pub struct Logical {
+    pub node: Node,
+    pub left: Box<Expr>,
+    pub operator: Token,
+    pub right: Box<Expr>,
 }
 
-impl Assignment {
-    pub fn new(name: String, val: Box<dyn Expr>) -> Self {
-	Assignment {
-	    name: Rc::new(name),
-	    val: val,
+impl Logical {
+    pub fn new(left: Box<Expr>, operator: Token, right: Box<Expr>) -> Self {
+	Logical {
+	    node: Node::new(left.span().to(right.span())),
+	    left,
+	    operator,
+	    right,
 	}
     }
+
+    pub fn print(&self) -> String {
+	format!("({} {} {})", self.operator.lexeme, self.left.print(), self.right.print())
+    }
+}
+
+//explicit conversion like int(c) or char(65)
+#[derive (Clone)]
+pub struct Cast {
+    pub node: Node,
+    pub target: Token,
+    pub expr: Box<Expr>,
 }
 
-impl Expr for Assignment {
-    fn print(&self) -> String {
-	format!("(= {} {})", self.name, self.val.print())
+impl Cast {
+    //the span takes in the closing parenthesis
+    pub fn new(span: Span, target: Token, expr: Box<Expr>) -> Self {
+	Cast {
+	    node: Node::new(span),
+	    target,
+	    expr,
+	}
     }
 
-    fn kind(&self) -> ExprType {
-	ExprType::Assignment
+    pub fn print(&self) -> String {
+	format!("({} {})", self.target.lexeme, self.expr.print())
     }
+}
+
+//c ? a : b, or if c then a else b
+#[derive (Clone)]
+pub struct Conditional {
+    pub node: Node,
+    pub keyword: Token,
+    pub cond: Box<Expr>,
+    pub then_expr: Box<Expr>,
+    pub else_expr: Box<Expr>,
+}
 
-    fn as_any(&self) -> &dyn Any {
-	self
+impl Conditional {
+    pub fn new(keyword: Token, cond: Box<Expr>,
+	       then_expr: Box<Expr>, else_expr: Box<Expr>) -> Self {
+	Conditional {
+	    node: Node::new(keyword.span().to(cond.span()).to(else_expr.span())),
+	    keyword,
+	    cond,
+	    then_expr,
+	    else_expr,
+	}
     }
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_assignment(&self)
+    pub fn print(&self) -> String {
+	format!("(? {} {} {})", self.cond.print(), self.then_expr.print(), self.else_expr.print())
     }
 }
 
-pub struct Variable {
-    pub name: Rc<String>,
+#[derive (Clone)]
+pub struct Param {
+    pub p_type: ValType,
+    pub name: Token,
+    //evaluated at call time, after the parameters before it are bound
+    pub default: Option<Box<Expr>>,
+    //int ...rest collects any leftover arguments into a list
+    pub variadic: bool,
+    //var int x is the caller's variable itself, not a copy of its value
+    pub by_ref: bool,
 }
 
-impl Variable {
-    pub fn new(name: String) -> Self {
-	Variable {
-	    name: Rc::new(name),
+#[derive (Clone)]
+pub enum FnBody {
+    //fn(int x) -> int: x * 2
+    Expr(Box<Expr>),
+    //function(int x) -> int ... end
+    Block(Vec<Stmt>),
+}
+
+//function max<T>(...), with an optional bound as in <T: Printable>
+#[derive (Clone)]
+pub struct TypeParam {
+    pub name: Token,
+    pub bound: Option<ValType>,
+}
+
+//shared between the Lambda node and every function value made from it,
+//the values can outlive the AST (e.g. at the prompt)
+#[derive (Clone)]
+pub struct FnDef {
+    pub name: Option<Token>,
+    pub keyword: Token,
+    pub type_params: Vec<TypeParam>,
+    pub params: Vec<Param>,
+    pub ret: Option<ValType>,
+    pub body: FnBody,
+    //a method has no node of its own, so the function has its own id
+    //for links::analyze to key what it finds by
+    pub id: NodeId,
+}
+
+fn print_params(params: &[Param]) -> String {
+    let params: Vec<String> = params.iter()
+	.map(|p| format!("{}{} {}{}{}", if p.by_ref { "var " } else { "" }, p.p_type,
+			 if p.variadic { "..." } else { "" },
+			 p.name.lexeme,
+			 match &p.default {
+			     Some(d) => format!(" = {}", d.print()),
+			     None => String::new(),
+			 }))
+	.collect();
+    params.join(", ")
+}
+
+impl FnDef {
+    pub fn print(&self) -> String {
+	let params = print_params(&self.params);
+	let ret = match &self.ret {
+	    Some(t) => format!(" -> {t}"),
+	    None => String::new(),
+	};
+	let body = match &self.body {
+	    FnBody::Expr(e) => e.print(),
+	    FnBody::Block(stmts) => {
+		let mut output = String::new();
+		output.push_str("(block\n");
+		for stmt in stmts {
+		    output.push_str(&stmt.print());
+		    output.push('\n');
+		}
+		output.push(')');
+		output
+	    },
+	};
+	let type_params: Vec<String> = self.type_params.iter()
+	    .map(|t| match &t.bound {
+		Some(b) => format!("{}: {b}", t.name.lexeme),
+		None => t.name.lexeme.to_string(),
+	    })
+	    .collect();
+	let type_params = match type_params.is_empty() {
+	    true => String::new(),
+	    false => format!("<{}>", type_params.join(", ")),
+	};
+	format!("(fn {type_params}({params}){ret} {body})")
+    }
+}
+
+//a method an interface asks for, everything but the body
+pub struct MethodSig {
+    pub name: Token,
+    pub params: Vec<Param>,
+    pub ret: Option<ValType>,
+}
+
+impl MethodSig {
+    pub fn print(&self) -> String {
+	format!("(method {} {})", self.name.lexeme, self.signature())
+    }
+
+    //(int x) -> str, for error messages
+    pub fn signature(&self) -> String {
+	let ret = match &self.ret {
+	    Some(t) => format!(" -> {t}"),
+	    None => String::new(),
+	};
+	format!("({}){ret}", print_params(&self.params))
+    }
+
+    //whether def can stand in for this, parameter for parameter
+    pub fn matches(&self, def: &FnDef) -> bool {
+	self.ret == def.ret && self.params.len() == def.params.len() &&
+	    self.params.iter().zip(def.params.iter())
+	    .all(|(a, b)| a.p_type == b.p_type && a.by_ref == b.by_ref && a.variadic == b.variadic)
+    }
+}
+
+#[derive (Clone)]
+pub struct Lambda {
+    pub node: Node,
+    pub def: Rc<FnDef>,
+}
+
+impl Lambda {
+    pub fn new(span: Span, def: FnDef) -> Self {
+	Lambda {
+	    node: Node::new(span),
+	    def: Rc::new(def),
 	}
     }
+
+    pub fn print(&self) -> String {
+	self.def.print()
+    }
+}
+
+#[derive (Clone)]
+pub struct Arg {
+    //set for name: value arguments
+    pub name: Option<Token>,
+    //where the argument starts, for error messages
+    pub start: Token,
+    pub value: Box<Expr>,
+}
+
+#[derive (Clone)]
+pub struct Call {
+    pub node: Node,
+    pub callee: Box<Expr>,
+    pub paren: Token,
+    pub args: Vec<Arg>,
 }
 
-impl Expr for Variable {
-    fn print(&self) -> String {
-	format!("{}", self.name)
+impl Call {
+    //paren is the closing one
+    pub fn new(callee: Box<Expr>, paren: Token, args: Vec<Arg>) -> Self {
+	Call {
+	    node: Node::new(callee.span().to(paren.span())),
+	    callee,
+	    paren,
+	    args,
+	}
     }
 
-    fn kind(&self) -> ExprType {
-	ExprType::Variable
+    pub fn print(&self) -> String {
+	let mut output = format!("(call {}", self.callee.print());
+	for arg in &self.args {
+	    output.push(' ');
+	    if let Some(n) = &arg.name {
+		output.push_str(&format!("{}: ", n.lexeme));
+	    }
+	    output.push_str(&arg.value.print());
+	}
+	output.push(')');
+	output
     }
+}
+
+#[derive (Clone)]
+pub struct Index {
+    pub node: Node,
+    pub object: Box<Expr>,
+    pub bracket: Token,
+    pub index: Box<Expr>,
+}
 
-    fn as_any(&self) -> &dyn Any {
-	self
+impl Index {
+    //the span takes in the closing bracket
+    pub fn new(span: Span, object: Box<Expr>, bracket: Token, index: Box<Expr>) -> Self {
+	Index {
+	    node: Node::new(span),
+	    object,
+	    bracket,
+	    index,
+	}
     }
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_variable(&self)
+    pub fn print(&self) -> String {
+	format!("([] {} {})", self.object.print(), self.index.print())
     }
 }
 
-pub struct Logical {
-    pub left: Box<dyn Expr>,
+//a..b, a..=b, either with an optional step
+#[derive (Clone)]
+pub struct Range {
+    pub node: Node,
+    pub start: Box<Expr>,
     pub operator: Token,
-    pub right: Box<dyn Expr>,
+    pub end: Box<Expr>,
+    pub step: Option<Box<Expr>>,
 }
 
-impl Logical {
-    pub fn new(left: Box<dyn Expr>, operator: Token, right: Box<dyn Expr>) -> Self {
-	Logical {
-	    left: left,
-	    operator: operator,
-	    right: right,
+impl Range {
+    pub fn new(start: Box<Expr>, operator: Token, end: Box<Expr>,
+	       step: Option<Box<Expr>>) -> Self {
+	let mut span = start.span().to(end.span());
+	if let Some(s) = &step {
+	    span = span.to(s.span());
+	}
+	Range {
+	    node: Node::new(span),
+	    start,
+	    operator,
+	    end,
+	    step,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	match &self.step {
+	    Some(s) => format!("({} {} {} {})", self.operator.lexeme, self.start.print(),
+			       self.end.print(), s.print()),
+	    None => format!("({} {} {})", self.operator.lexeme, self.start.print(), self.end.print()),
 	}
     }
 }
 
-impl Expr for Logical {
-    fn print(&self) -> String {
-	format!("({} {} {})", self.operator.lexeme, self.left.print(), self.right.print())
+#[derive (Clone)]
+pub struct Tuple {
+    pub node: Node,
+    pub paren: Token,
+    pub items: Vec<Expr>,
+}
+
+impl Tuple {
+    //the span takes in both parentheses
+    pub fn new(span: Span, paren: Token, items: Vec<Expr>) -> Self {
+	Tuple {
+	    node: Node::new(span),
+	    paren,
+	    items,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let items: Vec<String> = self.items.iter().map(|i| i.print()).collect();
+	format!("(tuple {})", items.join(" "))
+    }
+}
+
+#[derive (Clone)]
+pub struct List {
+    pub node: Node,
+    pub items: Vec<Expr>,
+}
+
+impl List {
+    //the span takes in both brackets
+    pub fn new(span: Span, items: Vec<Expr>) -> Self {
+	List {
+	    node: Node::new(span),
+	    items,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let items: Vec<String> = self.items.iter().map(|i| i.print()).collect();
+	format!("(list {})", items.join(" "))
+    }
+}
+
+//the left hand side of a var declaration or a tuple assignment
+#[derive (Clone)]
+pub enum Pattern {
+    //the id is for the resolver, a destructuring assignment looks the names up
+    Name(Option<ValType>, Token, NodeId),
+    Tuple(Token, Vec<Pattern>),
+}
+
+impl Pattern {
+    pub fn print(&self) -> String {
+	match self {
+	    Pattern::Name(Some(t), n, _) => format!("{t} {}", n.lexeme),
+	    Pattern::Name(None, n, _) => n.lexeme.to_string(),
+	    Pattern::Tuple(_, items) => {
+		let items: Vec<String> = items.iter().map(|i| i.print()).collect();
+		format!("({})", items.join(", "))
+	    },
+	}
+    }
+
+    //every name bound by the pattern, left to right
+    pub fn names(&self) -> Vec<&Token> {
+	match self {
+	    Pattern::Name(_, n, _) => vec![n],
+	    Pattern::Tuple(_, items) => items.iter().flat_map(|i| i.names()).collect(),
+	}
+    }
+}
+
+//(a, b) = (b, a)
+#[derive (Clone)]
+pub struct Destructure {
+    pub node: Node,
+    pub target: Pattern,
+    pub val: Box<Expr>,
+}
+
+impl Destructure {
+    //the span is the tuple that became the target, to the end of the value
+    pub fn new(span: Span, target: Pattern, val: Box<Expr>) -> Self {
+	Destructure {
+	    node: Node::new(span.to(val.span())),
+	    target,
+	    val,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(= {} {})", self.target.print(), self.val.print())
+    }
+}
+
+//object.name
+#[derive (Clone)]
+pub struct Get {
+    pub node: Node,
+    pub object: Box<Expr>,
+    pub name: Token,
+}
+
+impl Get {
+    pub fn new(object: Box<Expr>, name: Token) -> Self {
+	Get {
+	    node: Node::new(object.span().to(name.span())),
+	    object,
+	    name,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(. {} {})", self.object.print(), self.name.lexeme)
+    }
+}
+
+//object.name = val
+#[derive (Clone)]
+pub struct Set {
+    pub node: Node,
+    pub object: Box<Expr>,
+    pub name: Token,
+    pub val: Box<Expr>,
+}
+
+impl Set {
+    pub fn new(object: Box<Expr>, name: Token, val: Box<Expr>) -> Self {
+	Set {
+	    node: Node::new(object.span().to(val.span())),
+	    object,
+	    name,
+	    val,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(= (. {} {}) {})", self.object.print(), self.name.lexeme, self.val.print())
+    }
+}
+
+#[derive (Clone)]
+pub struct SelfExpr {
+    pub node: Node,
+    pub keyword: Token,
+}
+
+impl SelfExpr {
+    pub fn new(keyword: Token) -> Self {
+	SelfExpr {
+	    node: Node::new(keyword.span()),
+	    keyword,
+	}
     }
 
-    fn kind(&self) -> ExprType {
-	ExprType::Logical
+    pub fn print(&self) -> String {
+	"self".to_string()
     }
+}
+
+//super.method, always bound to self
+#[derive (Clone)]
+pub struct Super {
+    pub node: Node,
+    pub keyword: Token,
+    pub method: Token,
+}
 
-    fn as_any(&self) -> &dyn Any {
-	self
+impl Super {
+    pub fn new(keyword: Token, method: Token) -> Self {
+	Super {
+	    node: Node::new(keyword.span().to(method.span())),
+	    keyword,
+	    method,
+	}
     }
 
-    fn accept(&self, visitor: &mut dyn ExprVisitor) -> Result<Value, Box<dyn Error>> {
-	visitor.visit_logical(&self)
+    pub fn print(&self) -> String {
+	format!("(super {})", self.method.lexeme)
     }
 }
@@ -0,0 +1,276 @@
+use std::rc::Rc;
+use crate::expr::{Expr, Value, FnDef, FnBody, Literal, Lit, Tuple, List};
+use crate::stmt;
+use crate::stmt::Stmt;
+use crate::token::TokenType;
+use crate::interpreter::Interpreter;
+
+//works out what it can before running: an operator or cast whose
+//operands are all literals becomes the literal it comes to, and so do
+//and/or and ?: when the literal on the left decides them.
+//if true and if false become the branch that runs, and while false goes.
+//the interpreter itself does the sums, so they come out exactly as they
+//would have, and anything that fails (e.g. an overflow or a division by
+//zero) is left where it is to fail when it runs, where it can be caught.
+//runs after the checks, so they still see the code it takes out
+pub fn fold(ast: &mut Vec<Stmt>, i: &mut Interpreter) {
+    stmts(ast, i);
+}
+
+fn stmts(stmts: &mut Vec<Stmt>, i: &mut Interpreter) {
+    stmts.retain_mut(|s| stmt(s, i));
+}
+
+//false if the statement would never do anything, so it can go
+fn stmt(s: &mut Stmt, i: &mut Interpreter) -> bool {
+    let span = s.span();
+    let replacement = match s {
+	Stmt::Print(p) => {
+	    expr(&mut p.expr, i);
+	    None
+	},
+	Stmt::Expression(e) => {
+	    expr(&mut e.expr, i);
+	    None
+	},
+	Stmt::TypedDecl(d) => {
+	    opt_expr(&mut d.init, i);
+	    None
+	},
+	Stmt::VarDecl(d) => {
+	    expr(&mut d.init, i);
+	    None
+	},
+	Stmt::FnDecl(d) => {
+	    expr(&mut d.init, i);
+	    None
+	},
+	Stmt::Decls(d) => {
+	    stmts(&mut d.decls, i);
+	    None
+	},
+	Stmt::Block(b) => {
+	    stmts(&mut b.stmts, i);
+	    None
+	},
+	Stmt::Class(c) => {
+	    opt_expr(&mut c.superclass, i);
+	    for m in c.methods.iter_mut() {
+		function(m, i);
+	    }
+	    None
+	},
+	Stmt::Interface(_) | Stmt::Break(_) | Stmt::Continue(_) => None,
+	//both branches are blocks, so the one that's left keeps its scope
+	Stmt::If(f) => {
+	    expr(&mut f.cond, i);
+	    stmt(&mut f.then_branch, i);
+	    if let Some(e) = &mut f.else_branch {
+		stmt(e, i);
+	    }
+	    match bool_lit(f.cond.as_ref()) {
+		Some(true) => Some(std::mem::replace(f.then_branch.as_mut(), Stmt::Block(stmt::Block::new(Vec::new())))),
+		Some(false) => match f.else_branch.take() {
+		    Some(e) => Some(*e),
+		    None => return false,
+		},
+		None => None,
+	    }
+	},
+	Stmt::While(w) => {
+	    expr(&mut w.cond, i);
+	    stmt(&mut w.body, i);
+	    opt_expr(&mut w.increment, i);
+	    if bool_lit(w.cond.as_ref()) == Some(false) {
+		return false;
+	    }
+	    None
+	},
+	Stmt::Repeat(r) => {
+	    stmt(&mut r.body, i);
+	    expr(&mut r.cond, i);
+	    None
+	},
+	Stmt::Loop(l) => {
+	    stmt(&mut l.body, i);
+	    None
+	},
+	Stmt::ForIn(f) => {
+	    expr(&mut f.iterable, i);
+	    stmts(&mut f.body, i);
+	    None
+	},
+	Stmt::Throw(t) => {
+	    expr(&mut t.value, i);
+	    None
+	},
+	Stmt::Try(t) => {
+	    stmts(&mut t.body, i);
+	    stmts(&mut t.handler, i);
+	    None
+	},
+	Stmt::Assert(a) => {
+	    expr(&mut a.cond, i);
+	    opt_expr(&mut a.message, i);
+	    None
+	},
+	Stmt::Return(r) => {
+	    opt_expr(&mut r.value, i);
+	    None
+	},
+    };
+    if let Some(r) = replacement {
+	*s = r.at(span);
+    }
+    true
+}
+
+fn function(def: &mut Rc<FnDef>, i: &mut Interpreter) {
+    //the checker keeps the functions it saw, this one gets its own copy
+    let def = Rc::make_mut(def);
+    for p in def.params.iter_mut() {
+	opt_expr(&mut p.default, i);
+    }
+    match &mut def.body {
+	FnBody::Expr(e) => expr(e, i),
+	FnBody::Block(s) => stmts(s, i),
+    }
+}
+
+fn opt_expr(e: &mut Option<Box<Expr>>, i: &mut Interpreter) {
+    if let Some(e) = e {
+	expr(e, i);
+    }
+}
+
+//replaces e with what it comes to, if that's known now
+fn expr(e: &mut Expr, i: &mut Interpreter) {
+    if let Some(folded) = fold_expr(e, i) {
+	*e = *folded;
+    }
+}
+
+fn is_lit(e: &Expr) -> bool {
+    matches!(e, Expr::Literal(_))
+}
+
+fn bool_lit(e: &Expr) -> Option<bool> {
+    match e {
+	Expr::Literal(Literal{value: Lit::BoolLit(b), ..}) => Some(*b),
+	_ => None,
+    }
+}
+
+//what e comes to when it runs, if it doesn't fail and it's something a literal can be
+fn eval(e: &Expr, i: &mut Interpreter) -> Option<Box<Expr>> {
+    let value = match e.accept(i) {
+	Ok(Value::IntVal(n)) => Lit::IntLit(n),
+	Ok(Value::RealVal(r)) => Lit::RealLit(r),
+	Ok(Value::StrVal(s)) => Lit::StrLit(s),
+	Ok(Value::CharVal(c)) => Lit::CharLit(c),
+	Ok(Value::BoolVal(b)) => Lit::BoolLit(b),
+	_ => return None,
+    };
+    Some(Box::new(Expr::Literal(Literal::new(e.span(), value))))
+}
+
+fn take(e: &mut Box<Expr>) -> Box<Expr> {
+    let span = e.span();
+    std::mem::replace(e, Box::new(Expr::Literal(Literal::new(span, Lit::NilLit))))
+}
+
+//folds e's operands, then e itself if it can, returning what it's replaced with
+fn fold_expr(e: &mut Expr, i: &mut Interpreter) -> Option<Box<Expr>> {
+    let folds = match e {
+	Expr::Binary(b) => {
+	    expr(&mut b.left, i);
+	    expr(&mut b.right, i);
+	    is_lit(b.left.as_ref()) && is_lit(b.right.as_ref())
+	},
+	Expr::Unary(u) => {
+	    expr(&mut u.right, i);
+	    is_lit(u.right.as_ref())
+	},
+	Expr::Cast(c) => {
+	    expr(&mut c.expr, i);
+	    is_lit(c.expr.as_ref())
+	},
+	Expr::Grouping(g) => {
+	    expr(&mut g.expression, i);
+	    if is_lit(g.expression.as_ref()) {
+		return Some(take(&mut g.expression));
+	    }
+	    false
+	},
+	Expr::Logical(l) => {
+	    expr(&mut l.left, i);
+	    expr(&mut l.right, i);
+	    //the right side isn't even looked at when the left decides it
+	    match (&l.operator.t_type, bool_lit(l.left.as_ref())) {
+		(TokenType::Or, Some(true)) | (TokenType::And, Some(false)) => return Some(take(&mut l.left)),
+		_ => is_lit(l.left.as_ref()) && is_lit(l.right.as_ref()),
+	    }
+	},
+	Expr::Conditional(c) => {
+	    expr(&mut c.cond, i);
+	    expr(&mut c.then_expr, i);
+	    expr(&mut c.else_expr, i);
+	    match bool_lit(c.cond.as_ref()) {
+		Some(true) => return Some(take(&mut c.then_expr)),
+		Some(false) => return Some(take(&mut c.else_expr)),
+		None => false,
+	    }
+	},
+	Expr::Assignment(a) => {
+	    expr(&mut a.val, i);
+	    false
+	},
+	Expr::Lambda(l) => {
+	    function(&mut l.def, i);
+	    false
+	},
+	Expr::Call(c) => {
+	    expr(&mut c.callee, i);
+	    for a in c.args.iter_mut() {
+		expr(&mut a.value, i);
+	    }
+	    false
+	},
+	Expr::Index(x) => {
+	    expr(&mut x.object, i);
+	    expr(&mut x.index, i);
+	    false
+	},
+	Expr::Range(r) => {
+	    expr(&mut r.start, i);
+	    expr(&mut r.end, i);
+	    opt_expr(&mut r.step, i);
+	    false
+	},
+	Expr::Tuple(Tuple{items, ..}) | Expr::List(List{items, ..}) => {
+	    for item in items.iter_mut() {
+		expr(item, i);
+	    }
+	    false
+	},
+	Expr::Destructure(d) => {
+	    expr(&mut d.val, i);
+	    false
+	},
+	Expr::Get(g) => {
+	    expr(&mut g.object, i);
+	    false
+	},
+	Expr::Set(st) => {
+	    expr(&mut st.object, i);
+	    expr(&mut st.val, i);
+	    false
+	},
+	Expr::Literal(_) | Expr::Variable(_) | Expr::SelfExpr(_) | Expr::Super(_) => false,
+    };
+    if folds {
+	eval(e, i)
+    } else {
+	None
+    }
+}
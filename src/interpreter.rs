@@ -1,42 +1,254 @@
+use std::collections::HashMap;
 use std::error::Error;
 use std::rc::Rc;
 use std::cell::RefCell;
-use crate::stmt::{Stmt, StmtType, StmtVisitor};
-use crate::environment::Environment;
-use crate::token::TokenType;
+use crate::stmt;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::environment::{Environment, Place};
+use crate::token::{Token, TokenType};
+use crate::resolver::{Depth, Depths};
+use crate::links::LinkDepths;
+use crate::{ErrorKind, RuntimeError, AssertionError};
 use crate::expr;
-use crate::expr::{ExprVisitor, Value};
+use crate::expr::{ExprVisitor, Value, ValType, Types, Function, FnDef, FnBody, Class, Instance, NodeId};
 
+fn error(kind: ErrorKind, t: &Token, msg: &str) -> Box<dyn Error> {
+    Box::new(RuntimeError::new(kind, format!("{msg} at '{}'", t.lexeme), t.line))
+}
+
+//return unwinds out of the function body through the Err path,
+//it isn't a RuntimeError so try/catch leaves it alone
+#[derive (Debug)]
+struct ReturnValue {
+    value: Value,
+}
+
+impl std::fmt::Display for ReturnValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "return {}", self.value)
+    }
+}
+
+impl Error for ReturnValue {}
+
+//an evaluated argument, the token it starts at for error messages
+//and for a var parameter the variable that was passed
+type ArgVal = (Token, Value, Option<Place>);
+
+//the environment a call's body runs in and what its type parameters stand for
+type BoundArgs = (Rc<RefCell<Environment>>, Types);
+
+//a call in tail position, unwound back to call_function
+//so the host stack doesn't grow
+#[derive (Debug)]
+struct TailCall {
+    func: Rc<Function>,
+    args: Vec<ArgVal>,
+    named: Vec<ArgVal>,
+    paren: Token,
+    //for a class's init, the instance being made, which is what the call gives back
+    construct: Option<Value>,
+}
+
+impl std::fmt::Display for TailCall {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "tail call to {:?}", self.func)
+    }
+}
+
+impl Error for TailCall {}
+
+//what a call turns into before it's made, constructing an
+//instance of a class with no init doesn't need a call at all
+enum Prepared {
+    Call(TailCall),
+    Done(Value),
+}
+
+//break and continue unwind to the nearest loop the same way
 #[derive (Debug)]
-struct EvalError {}
+struct LoopSignal {
+    keyword: Token,
+    //None for the innermost loop
+    label: Option<Token>,
+}
 
-impl std::fmt::Display for EvalError {
+impl std::fmt::Display for LoopSignal {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-	write!(f, "evaluation error")
+	write!(f, "{} outside of a loop", self.keyword.lexeme)
+    }
+}
+
+impl Error for LoopSignal {}
+
+//every function in a tail call chain returns the same value,
+//so each one's declared return type gets checked against it
+fn check_returns(defs: &[(Rc<FnDef>, Types)], value: &Value) -> Result<(), Box<dyn Error>> {
+    for (d, types) in defs.iter() {
+	let t = match &d.ret {
+	    Some(t) => t,
+	    None => continue,
+	};
+	let expect = t.instantiate(types);
+	if !value.is(&expect) {
+	    return Err(error(ErrorKind::Type, &d.keyword,
+			     &format!("function should return {expect}{} but returned {}",
+				      since(t, types, &fn_name(d)), value.val_type())));
+	}
+    }
+    Ok(())
+}
+
+fn fn_name(def: &FnDef) -> String {
+    match &def.name {
+	Some(n) => format!("'{}'", n.lexeme),
+	None => "anonymous function".to_string(),
+    }
+}
+
+//", since T is int in this call to 'f'," when t has a type parameter that's been decided
+fn since(t: &ValType, types: &Types, fn_name: &str) -> String {
+    match t.type_param().and_then(|n| types.get(n).map(|v| (n, v))) {
+	Some((n, v)) => format!(", since {n} is {v} in this call to {fn_name},"),
+	None => String::new(),
     }
 }
 
-impl Error for EvalError {}
+//work out what the type parameter in t stands for from a value passed for it.
+//the first value to reach one decides it and has to meet its bound, except
+//that instances widen it to the nearest class they all are, so the order
+//of the arguments doesn't matter. classes has the class each one is so far
+fn infer(t: &ValType, value: &Value, def: &FnDef, types: &mut Types,
+	 classes: &mut HashMap<Rc<String>, Rc<Class>>, at: &Token) -> Result<(), Box<dyn Error>> {
+    match (t, value) {
+	(ValType::Param(n), v) if !types.contains_key(n) => {
+	    check_bound(n, &v.val_type(), |b| v.is(b), def, at)?;
+	    types.insert(n.clone(), v.val_type());
+	    if let Value::InstanceVal(i) = v {
+		classes.insert(n.clone(), i.borrow().class.clone());
+	    }
+	},
+	(ValType::Param(n), Value::InstanceVal(i)) => {
+	    let so_far = match classes.get(n) {
+		Some(c) => c.clone(),
+		//decided by something that isn't an instance, the argument's own check fails
+		None => return Ok(()),
+	    };
+	    let mut class = Some(i.borrow().class.clone());
+	    while let Some(c) = class {
+		if so_far.is_a(&c.name.lexeme) {
+		    let t = ValType::Object(c.name.lexeme.clone());
+		    check_bound(n, &t, |b| match b {
+			ValType::Object(b) => c.is_a(b),
+			_ => false,
+		    }, def, at)?;
+		    types.insert(n.clone(), t);
+		    classes.insert(n.clone(), c);
+		    break;
+		}
+		class = c.superclass.clone();
+	    }
+	},
+	(ValType::List(Some(t)), Value::ListVal(items)) => {
+	    for i in items.iter() {
+		infer(t, i, def, types, classes, at)?;
+	    }
+	},
+	_ => {},
+    }
+    Ok(())
+}
+
+//whether the type parameter n can be t, fits says if something of type t fits a bound
+fn check_bound(n: &Rc<String>, t: &ValType, fits: impl Fn(&ValType) -> bool,
+	       def: &FnDef, at: &Token) -> Result<(), Box<dyn Error>> {
+    let bound = def.type_params.iter()
+	.find(|p| p.name.lexeme == *n)
+	.and_then(|p| p.bound.as_ref());
+    match bound {
+	Some(b) if !fits(b) => {
+	    Err(error(ErrorKind::Type, at, &format!("{n} is {t} in this call to {}, which isn't {b}", fn_name(def))))
+	},
+	_ => Ok(()),
+    }
+}
+
+//an operator used on values it doesn't work for
+fn mismatch(op: &Token, left: &Value, right: &Value) -> Box<dyn Error> {
+    error(ErrorKind::Type, op, &format!("can't apply '{}' to {} and {}", op.lexeme, left.val_type(), right.val_type()))
+}
+
+//for what the parser rules out, like a super that isn't a class
+fn impossible() -> Box<dyn Error> {
+    Box::new(RuntimeError::new(ErrorKind::Internal,
+			       "theoretically impossible error in the interpreter".to_string(), 0))
+}
 
 pub struct Interpreter {
     cur_env: Rc<RefCell<Environment>>,
+    globals: Rc<RefCell<Environment>>,
+    //restrict char values to a single byte (PETSCII/ASCII)
+    pub byte_chars: bool,
+    //assert statements are skipped entirely when this is off
+    pub asserts: bool,
+    //how many try blocks we're inside of in the current function,
+    //a return in one isn't a tail call since the catch has to stay live
+    in_try: usize,
+    //the static links of the function call that's running, the scope
+    //it was made in first, empty at the top level
+    links: Vec<Rc<RefCell<Environment>>>,
+    //where the resolver found each local variable, for every line so far
+    depths: Depths,
+    //how many static links each function needs, for every line so far
+    link_depths: LinkDepths,
 }
 
 impl Interpreter {
     pub fn new() -> Self {
+	let globals = Rc::new(RefCell::new(Environment::new(None)));
 	Interpreter {
-	    cur_env: Rc::new(RefCell::new(Environment::new(None))),
+	    cur_env: globals.clone(),
+	    globals,
+	    byte_chars: false,
+	    asserts: true,
+	    in_try: 0,
+	    links: Vec::new(),
+	    depths: Depths::new(),
+	    link_depths: LinkDepths::new(),
 	}
     }
 
+    //where the resolver found the variables of the code about to run,
+    //kept for as long as the functions in it can be called
+    pub fn resolved(&mut self, depths: Depths) {
+	self.depths.extend(depths);
+    }
+
+    //how far out the functions in the code about to run reach, kept
+    //for as long as they can be called
+    pub fn linked(&mut self, link_depths: LinkDepths) {
+	self.link_depths.extend(link_depths);
+    }
+
     pub fn interpret(&mut self, ast: Vec<Stmt>) -> Result<(), Box<dyn Error>> {
 	for stmt in ast.iter() {
-	    stmt.accept(self)?;
+	    match stmt.accept(self) {
+		Ok(_) => {},
+		Err(e) => {
+		    //anything that gets this far was never caught
+		    if let Some(err) = e.downcast_ref::<RuntimeError>() {
+			crate::report(err.line, &format!("({})", err.kind), &err.msg);
+		    } else if let Some(err) = e.downcast_ref::<AssertionError>() {
+			crate::report(err.line, "(assert)", &err.msg);
+		    }
+		    return Err(e);
+		},
+	    }
 	}
 	Ok(())
     }
 
-    fn exec_block(&mut self, s: &Vec<Stmt>, e: Rc<RefCell<Environment>>) -> Result<(), Box<dyn Error>> {
+    fn exec_block(&mut self, s: &[Stmt], e: Rc<RefCell<Environment>>) -> Result<(), Box<dyn Error>> {
 	let previous = self.cur_env.clone();
 	self.cur_env = e;
 	for stmt in s.iter() {
@@ -51,13 +263,467 @@ impl Interpreter {
 	self.cur_env = previous;
 	Ok(())
     }
+
+    fn condition(&mut self, k: &Token, c: &expr::Expr) -> Result<bool, Box<dyn Error>> {
+	match c.accept(self)? {
+	    Value::BoolVal(b) => Ok(b),
+	    _ => Err(error(ErrorKind::Type, k, "conditional expression must be boolean")),
+	}
+    }
+
+    //what one run of a loop body means for the loop,
+    //Ok(false) when it broke out, a break or continue meant for
+    //a loop further out keeps unwinding
+    fn loop_step(&mut self, label: &Option<Token>,
+		 result: Result<(), Box<dyn Error>>) -> Result<bool, Box<dyn Error>> {
+	match result {
+	    Ok(_) => Ok(true),
+	    Err(e) => match e.downcast::<LoopSignal>() {
+		Ok(sig) => match (&sig.label, label) {
+		    (Some(target), Some(l)) if target.lexeme != l.lexeme => Err(sig),
+		    (Some(_), None) => Err(sig),
+		    _ => Ok(sig.keyword.t_type == TokenType::Continue),
+		},
+		Err(e) => Err(e),
+	    },
+	}
+    }
+
+    //all the condition checked loops, while checks before the body,
+    //repeat after it and loop never does
+    fn run_loop(&mut self, k: &Token, label: &Option<Token>, before: Option<&expr::Expr>, body: &Stmt,
+		incr: Option<&expr::Expr>, until: Option<&expr::Expr>) -> Result<(), Box<dyn Error>> {
+	loop {
+	    let go = match before {
+		Some(c) => self.condition(k, c)?,
+		None => true,
+	    };
+	    if !go {
+		break;
+	    }
+	    let result = body.accept(self);
+	    if !self.loop_step(label, result)? {
+		break;
+	    }
+	    if let Some(i) = incr {
+		i.accept(self)?;
+	    }
+	    let done = match until {
+		Some(c) => self.condition(k, c)?,
+		None => false,
+	    };
+	    if done {
+		break;
+	    }
+	}
+	Ok(())
+    }
+
+    //where the resolver found the variable the node id uses, globals aren't
+    //counted since there may be more of them by the time it's used
+    fn scope(&self, id: NodeId) -> Rc<RefCell<Environment>> {
+	match self.depths.get(&id) {
+	    Some(d) => self.locate(d),
+	    None => self.globals.clone(),
+	}
+    }
+
+    //a frame's environment has no parent, anything outside the function
+    //is reached through its static links
+    fn locate(&self, d: &Depth) -> Rc<RefCell<Environment>> {
+	let start = match d.fns {
+	    0 => &self.cur_env,
+	    n => self.links.get(n - 1).expect("missing static link, fix links::analyze"),
+	};
+	Environment::ancestor(start, d.from_link)
+    }
+
+    //a variable keeps the type it was declared with
+    fn assign(&mut self, env: Rc<RefCell<Environment>>, name: &Token, r_value: &Value) -> Result<(), Box<dyn Error>> {
+	let l_value = (*env).borrow().get(name)?;
+	//an object variable can hold instances of its class and its subclasses
+	let declared = (*env).borrow().declared(name)?;
+	if !r_value.is(&declared) {
+	    return Err(error(ErrorKind::Type, name,
+			     &format!("type mismatch in {:?} and {:?}", l_value, r_value)));
+	}
+	(*env).borrow_mut().assign(name, r_value)
+    }
+
+    //unpack v into the names in p, defining them for var
+    //declarations and assigning them otherwise
+    fn bind_pattern(&mut self, p: &expr::Pattern, v: Value, define: bool) -> Result<(), Box<dyn Error>> {
+	match p {
+	    expr::Pattern::Name(t, n, id) => {
+		match t {
+		    Some(t) if !v.is(t) => {
+			return Err(error(ErrorKind::Type, n, &format!("mismatched types {} and {:?}", n.lexeme, v)));
+		    },
+		    _ => {},
+		}
+		match (define, t) {
+		    (true, Some(t)) => (*self.cur_env).borrow_mut().define_typed(&n.lexeme, v, t.clone()),
+		    (true, None) => (*self.cur_env).borrow_mut().define(&n.lexeme, v),
+		    (false, _) => self.assign(self.scope(*id), n, &v)?,
+		}
+		Ok(())
+	    },
+	    expr::Pattern::Tuple(paren, items) => {
+		let values = match &v {
+		    Value::TupleVal(t) if t.len() == items.len() => t.clone(),
+		    Value::TupleVal(t) => {
+			return Err(error(ErrorKind::Type, paren,
+					 &format!("can't unpack a tuple of {} into {} names", t.len(), items.len())));
+		    },
+		    v => return Err(error(ErrorKind::Type, paren, &format!("can't unpack a value of type {}", v.val_type()))),
+		};
+		for (p, v) in items.iter().zip(values.iter()) {
+		    self.bind_pattern(p, v.clone(), define)?;
+		}
+		Ok(())
+	    },
+	}
+    }
+
+    //evaluate e with env as the current environment
+    fn eval_in(&mut self, e: &expr::Expr, env: Rc<RefCell<Environment>>) -> Result<Value, Box<dyn Error>> {
+	let previous = self.cur_env.clone();
+	self.cur_env = env;
+	let result = e.accept(self);
+	self.cur_env = previous;
+	result
+    }
+
+    //the caller's links are back once the call returns, however it returns
+    fn call_function(&mut self, call: TailCall) -> Result<Value, Box<dyn Error>> {
+	let links = std::mem::take(&mut self.links);
+	let result = self.run_calls(call);
+	self.links = links;
+	result
+    }
+
+    //calls in tail position don't recurse, visit_return hands back a
+    //TailCall and this loops on it, so they run in constant host stack
+    fn run_calls(&mut self, call: TailCall) -> Result<Value, Box<dyn Error>> {
+	let mut call = call;
+	//functions that tail called still get their return type checked
+	let mut owed = Vec::<(Rc<FnDef>, Types)>::new();
+	//an init in the chain cuts it in two, the calls before it get the
+	//instance and the ones after get whatever init's own chain returns
+	let mut constructed = Vec::<(Value, Vec<(Rc<FnDef>, Types)>)>::new();
+	loop {
+	    let def = call.func.def.clone();
+	    if let Some(instance) = call.construct.take() {
+		constructed.push((instance, std::mem::take(&mut owed)));
+	    }
+	    //the frame of the call before is dropped here unless a function
+	    //made in it links to it, so a long chain of tail calls doesn't
+	    //pile them up
+	    self.links = call.func.links.clone();
+	    let (env, types) = self.bind_args(&call.func, call.args, call.named, &call.paren)?;
+
+	    //a try around the call doesn't make the callee's returns non-tail
+	    let in_try = self.in_try;
+	    self.in_try = 0;
+	    let result = match &def.body {
+		FnBody::Expr(e) => {
+		    let previous = self.cur_env.clone();
+		    self.cur_env = env;
+		    let result = self.tail_eval(e.as_ref());
+		    self.cur_env = previous;
+		    result
+		},
+		FnBody::Block(stmts) => match self.exec_block(stmts, env) {
+		    Ok(_) => Ok(Value::NilVal),
+		    Err(e) => match e.downcast::<ReturnValue>() {
+			Ok(r) => Ok(r.value),
+			Err(e) => Err(e),
+		    },
+		},
+	    };
+	    self.in_try = in_try;
+
+	    let value = match result {
+		Ok(v) => v,
+		Err(e) => {
+		    let next = e.downcast::<TailCall>()?;
+		    if !owed.iter().any(|(d, _)| Rc::ptr_eq(d, &def)) {
+			owed.push((def, types));
+		    }
+		    call = *next;
+		    continue;
+		},
+	    };
+
+	    owed.push((def, types));
+	    check_returns(&owed, &value)?;
+	    let mut value = value;
+	    while let Some((instance, owed)) = constructed.pop() {
+		value = instance;
+		check_returns(&owed, &value)?;
+	    }
+	    return Ok(value);
+	}
+    }
+
+    //args are the positional arguments, named the name: value ones,
+    //each along with the token it starts at for error messages
+    fn bind_args(&mut self, func: &Function, args: Vec<ArgVal>,
+		 named: Vec<ArgVal>, paren: &Token)
+		 -> Result<BoundArgs, Box<dyn Error>> {
+	let def = &func.def;
+	let variadic = def.params.last().is_some_and(|p| p.variadic);
+	let fixed = def.params.len() - variadic as usize;
+
+	let mut slots: Vec<Option<ArgVal>> = vec![None; fixed];
+	let mut rest = Vec::<ArgVal>::new();
+	for (i, arg) in args.into_iter().enumerate() {
+	    if i < fixed {
+		slots[i] = Some(arg);
+	    } else if variadic {
+		rest.push(arg);
+	    } else {
+		return Err(error(ErrorKind::Argument, &arg.0,
+				 &format!("unexpected argument {} in call to {}, which takes {fixed}", i + 1, fn_name(def))));
+	    }
+	}
+	for (name, value, place) in named {
+	    match def.params.iter().position(|p| p.name.lexeme == name.lexeme) {
+		Some(i) if i >= fixed => {
+		    return Err(error(ErrorKind::Argument, &name,
+				     &format!("variadic argument '{}' can't be passed by name", name.lexeme)));
+		},
+		Some(i) if slots[i].is_some() => {
+		    return Err(error(ErrorKind::Argument, &name,
+				     &format!("argument '{}' given more than once", name.lexeme)));
+		},
+		Some(i) => slots[i] = Some((name, value, place)),
+		None => {
+		    return Err(error(ErrorKind::Argument, &name,
+				     &format!("unexpected argument '{}' in call to {}", name.lexeme, fn_name(def))));
+		},
+	    }
+	}
+
+	let env = Rc::new(RefCell::new(Environment::new(None)));
+	let mut types = Types::new();
+	let mut classes = HashMap::new();
+	for (param, slot) in def.params.iter().zip(slots) {
+	    let (at, value, place) = match slot {
+		Some(arg) => arg,
+		None => match &param.default {
+		    Some(d) => (paren.clone(), self.eval_in(d.as_ref(), env.clone())?, None),
+		    None => {
+			return Err(error(ErrorKind::Argument, paren,
+					 &format!("missing argument '{}' in call to {}", param.name.lexeme, fn_name(def))));
+		    },
+		},
+	    };
+	    infer(&param.p_type, &value, def, &mut types, &mut classes, &at)?;
+	    let p_type = param.p_type.instantiate(&types);
+	    if !value.is(&p_type) {
+		return Err(error(ErrorKind::Type, &at,
+				 &format!("argument '{}' should be {p_type}{} but got {}",
+					  param.name.lexeme, since(&param.p_type, &types, &fn_name(def)), value.val_type())));
+	    }
+	    match (param.by_ref, place) {
+		(true, Some(place)) => env.borrow_mut().define_ref(&param.name.lexeme, place),
+		(true, None) => {
+		    return Err(error(ErrorKind::Argument, &at,
+				     &format!("argument '{}' is a var parameter and needs a variable", param.name.lexeme)));
+		},
+		(false, _) => env.borrow_mut().define_typed(&param.name.lexeme, value, p_type),
+	    }
+	}
+	if variadic {
+	    let param = &def.params[fixed];
+	    let mut items = Vec::<Value>::new();
+	    for (at, value, _) in rest {
+		infer(&param.p_type, &value, def, &mut types, &mut classes, &at)?;
+		let p_type = param.p_type.instantiate(&types);
+		if !value.is(&p_type) {
+		    return Err(error(ErrorKind::Type, &at,
+				     &format!("argument '{}' should be {p_type}{} but got {}",
+					      param.name.lexeme, since(&param.p_type, &types, &fn_name(def)), value.val_type())));
+		}
+		items.push(value);
+	    }
+	    env.borrow_mut().define(&param.name.lexeme, Value::ListVal(Rc::new(items)));
+	}
+
+	Ok((env, types))
+    }
+
+    //evaluate the arguments and hand back the call without making it
+    fn prepare_call(&mut self, e: &expr::Call) -> Result<Prepared, Box<dyn Error>> {
+	//calling a class makes an instance and runs its init on it
+	let (func, construct) = match e.callee.accept(self)? {
+	    Value::FnVal(f) => (f, None),
+	    Value::ClassVal(c) => {
+		let instance = Value::InstanceVal(Rc::new(RefCell::new(Instance::new(c.clone()))));
+		match c.find_method("init") {
+		    Some(init) => (self.bind(&init, &instance), Some(instance)),
+		    None if e.args.is_empty() => return Ok(Prepared::Done(instance)),
+		    None => {
+			return Err(error(ErrorKind::Argument, &e.paren,
+					 &format!("class '{}' has no init and takes no arguments", c.name.lexeme)));
+		    },
+		}
+	    },
+	    v => return Err(error(ErrorKind::Type, &e.paren, &format!("can't call a value of type {}", v.val_type()))),
+	};
+	let mut args = Vec::<ArgVal>::new();
+	let mut named = Vec::<ArgVal>::new();
+	for arg in &e.args {
+	    let value = arg.value.accept(self)?;
+	    //a var parameter gets where the variable lives as well
+	    let by_ref = match &arg.name {
+		Some(n) => func.def.params.iter().any(|p| p.by_ref && p.name.lexeme == n.lexeme),
+		None => func.def.params.get(args.len()).is_some_and(|p| p.by_ref),
+	    };
+	    let place = match arg.value.as_ref() {
+		expr::Expr::Variable(v) if by_ref => Environment::locate(&self.scope(arg.value.id()), &v.name),
+		_ => None,
+	    };
+	    match &arg.name {
+		Some(n) => named.push((n.clone(), value, place)),
+		None => args.push((arg.start.clone(), value, place)),
+	    }
+	}
+	Ok(Prepared::Call(TailCall {
+	    func,
+	    args,
+	    named,
+	    paren: e.paren.clone(),
+	    construct,
+	}))
+    }
+
+    //the method bound to an instance, its body sees the instance as self,
+    //which goes between the method and the scope its first link points to
+    fn bind(&self, method: &Rc<Function>, instance: &Value) -> Rc<Function> {
+	let Some(outer) = method.links.first() else {
+	    //it doesn't use self or anything else outside it
+	    return method.clone();
+	};
+	let env = Rc::new(RefCell::new(Environment::new(Some(outer.clone()))));
+	env.borrow_mut().define(&Rc::new("self".to_string()), instance.clone());
+	Rc::new(Function {
+	    def: method.def.clone(),
+	    links: std::iter::once(env).chain(method.links[1..].iter().cloned()).collect(),
+	})
+    }
+
+    //the links for a function made here, the current scope and then as
+    //many of the running function's as it reaches through
+    fn links_for(&self, def: &FnDef, scope: Rc<RefCell<Environment>>) -> Vec<Rc<RefCell<Environment>>> {
+	let depth = self.link_depths.get(&def.id).copied().unwrap_or(0);
+	std::iter::once(scope).chain(self.links.iter().cloned()).take(depth).collect()
+    }
+
+    //an operator on an instance calls the method of its class named for the
+    //operator, and the comparisons and != can be worked out from < and ==
+    fn overloaded(&mut self, op: &Token, left: Value, right: Value) -> Result<Value, Box<dyn Error>> {
+	let class = match &left {
+	    Value::InstanceVal(i) => i.borrow().class.clone(),
+	    _ => return Err(mismatch(op, &left, &right)),
+	};
+	if let Some(m) = class.find_method(&op.lexeme) {
+	    return match op.t_type {
+		TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash => {
+		    self.call_operator(op, &m, &left, right)
+		},
+		_ => Ok(Value::BoolVal(self.compare(op, &op.lexeme, &class, &left, &right)?)),
+	    };
+	}
+	let b = match op.t_type {
+	    TokenType::EqualEqual => left == right,
+	    TokenType::BangEqual => !self.compare(op, "==", &class, &left, &right)?,
+	    TokenType::GreaterEqual => !self.compare(op, "<", &class, &left, &right)?,
+	    TokenType::LessEqual => {
+		self.compare(op, "<", &class, &left, &right)? || self.compare(op, "==", &class, &left, &right)?
+	    },
+	    TokenType::Greater => {
+		!self.compare(op, "<", &class, &left, &right)? && !self.compare(op, "==", &class, &left, &right)?
+	    },
+	    _ => {
+		return Err(error(ErrorKind::Type, op,
+				 &format!("operator '{}' isn't defined for {}", op.lexeme, class.name.lexeme)));
+	    },
+	};
+	Ok(Value::BoolVal(b))
+    }
+
+    //a comparison through an operator method, without one == is whether they're the same instance.
+    //so is == or != with something the method doesn't take, like nil
+    fn compare(&mut self, op: &Token, name: &str, class: &Class,
+	       left: &Value, right: &Value) -> Result<bool, Box<dyn Error>> {
+	let method = match class.find_method(name) {
+	    Some(m) => m,
+	    None if name == "==" => return Ok(left == right),
+	    None => {
+		return Err(error(ErrorKind::Type, op,
+				 &format!("operator '{}' isn't defined for {}", op.lexeme, class.name.lexeme)));
+	    },
+	};
+	let takes = method.def.params.first().is_some_and(|p| right.is(&p.p_type));
+	match name {
+	    "==" if !takes => return Ok(left == right),
+	    "!=" if !takes => return Ok(left != right),
+	    _ => {},
+	}
+	match self.call_operator(op, &method, left, right.clone())? {
+	    Value::BoolVal(b) => Ok(b),
+	    v => Err(error(ErrorKind::Type, op,
+			   &format!("operator '{name}' of {} should return bool but returned {}",
+				    class.name.lexeme, v.val_type()))),
+	}
+    }
+
+    fn call_operator(&mut self, op: &Token, method: &Rc<Function>,
+		     left: &Value, right: Value) -> Result<Value, Box<dyn Error>> {
+	let func = self.bind(method, left);
+	self.call_function(TailCall {
+	    func,
+	    args: vec![(op.clone(), right, None)],
+	    named: Vec::new(),
+	    paren: op.clone(),
+	    construct: None,
+	})
+    }
+
+    //evaluate an expression in tail position, a call there comes back
+    //as a TailCall error for call_function to pick up
+    fn tail_eval(&mut self, e: &expr::Expr) -> Result<Value, Box<dyn Error>> {
+	match e {
+	    expr::Expr::Call(call) => {
+		match self.prepare_call(call)? {
+		    Prepared::Call(c) => Err(Box::new(c)),
+		    Prepared::Done(v) => Ok(v),
+		}
+	    },
+	    expr::Expr::Conditional(c) => {
+		match c.cond.accept(self)? {
+		    Value::BoolVal(true) => self.tail_eval(c.then_expr.as_ref()),
+		    Value::BoolVal(false) => self.tail_eval(c.else_expr.as_ref()),
+		    _ => Err(error(ErrorKind::Type, &c.keyword, "conditional expression must be boolean")),
+		}
+	    },
+	    expr::Expr::Grouping(g) => self.tail_eval(g.expression.as_ref()),
+	    _ => e.accept(self),
+	}
+    }
 }
 
-impl ExprVisitor for Interpreter {
+impl ExprVisitor<Result<Value, Box<dyn Error>>> for Interpreter {
     fn visit_binary(&mut self, e: &expr::Binary) -> Result<Value, Box<dyn Error>> {
 	let left = e.left.accept(self)?;
 	let right = e.right.accept(self)?;
-	//todo: decide whether to saturate or wrap arithmetic
+	//an instance on the left decides what the operator means
+	if let Value::InstanceVal(_) = left {
+	    return self.overloaded(&e.operator, left, right);
+	}
+	//int arithmetic is checked, overflow is a catchable error
 	match e.operator.t_type {
 	    TokenType::Plus => {
 		match (left, right) {
@@ -65,13 +731,14 @@ impl ExprVisitor for Interpreter {
 			Ok(Value::RealVal(l + r))
 		    },
 		    (Value::IntVal(l), Value::IntVal(r)) => {
-			Ok(Value::IntVal(l + r))
+			match l.checked_add(r) {
+			    Some(i) => Ok(Value::IntVal(i)),
+			    None => Err(error(ErrorKind::Overflow, &e.operator, "integer overflow")),
+			}
 		    },
 		    //todo: string concat
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -81,28 +748,32 @@ impl ExprVisitor for Interpreter {
 			Ok(Value::RealVal(l - r))
 		    },
 		    (Value::IntVal(l), Value::IntVal(r)) => {
-			Ok(Value::IntVal(l - r))
+			match l.checked_sub(r) {
+			    Some(i) => Ok(Value::IntVal(i)),
+			    None => Err(error(ErrorKind::Overflow, &e.operator, "integer overflow")),
+			}
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
 	    TokenType::Slash => {
 		match (left, right) {
 		    (Value::RealVal(l), Value::RealVal(r)) => {
-			//TODO: divide by zero handling
+			if r == 0.0 {
+			    return Err(error(ErrorKind::DivideByZero, &e.operator, "division by zero"));
+			}
 			Ok(Value::RealVal(l / r))
 		    },
 		    (Value::IntVal(l), Value::IntVal(r)) => {
-			Ok(Value::IntVal(l / r))
+			match l.checked_div(r) {
+			    Some(i) => Ok(Value::IntVal(i)),
+			    None => Err(error(ErrorKind::DivideByZero, &e.operator, "division by zero")),
+			}
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -112,12 +783,13 @@ impl ExprVisitor for Interpreter {
 			Ok(Value::RealVal(l * r))
 		    },
 		    (Value::IntVal(l), Value::IntVal(r)) => {
-			Ok(Value::IntVal(l * r))
+			match l.checked_mul(r) {
+			    Some(i) => Ok(Value::IntVal(i)),
+			    None => Err(error(ErrorKind::Overflow, &e.operator, "integer overflow")),
+			}
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -129,10 +801,11 @@ impl ExprVisitor for Interpreter {
 		    (Value::IntVal(l), Value::IntVal(r)) => {
 			Ok(Value::BoolVal(l > r))
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (Value::CharVal(l), Value::CharVal(r)) => {
+			Ok(Value::BoolVal(l > r))
+		    },
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -144,10 +817,11 @@ impl ExprVisitor for Interpreter {
 		    (Value::IntVal(l), Value::IntVal(r)) => {
 			Ok(Value::BoolVal(l >= r))
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (Value::CharVal(l), Value::CharVal(r)) => {
+			Ok(Value::BoolVal(l >= r))
+		    },
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -159,10 +833,11 @@ impl ExprVisitor for Interpreter {
 		    (Value::IntVal(l), Value::IntVal(r)) => {
 			Ok(Value::BoolVal(l < r))
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (Value::CharVal(l), Value::CharVal(r)) => {
+			Ok(Value::BoolVal(l < r))
+		    },
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -174,10 +849,11 @@ impl ExprVisitor for Interpreter {
 		    (Value::IntVal(l), Value::IntVal(r)) => {
 			Ok(Value::BoolVal(l <= r))
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (Value::CharVal(l), Value::CharVal(r)) => {
+			Ok(Value::BoolVal(l <= r))
+		    },
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -189,14 +865,18 @@ impl ExprVisitor for Interpreter {
 		    (Value::IntVal(l), Value::IntVal(r)) => {
 			Ok(Value::BoolVal(l == r))
 		    },
+		    (Value::CharVal(l), Value::CharVal(r)) => {
+			Ok(Value::BoolVal(l == r))
+		    },
 		    (Value::BoolVal(l), Value::BoolVal(r)) => {
 			Ok(Value::BoolVal(l == r))
 		    },
+		    (Value::StrVal(l), Value::StrVal(r)) => {
+			Ok(Value::BoolVal(l == r))
+		    },
 		    (Value::NilVal, Value::NilVal) => Ok(Value::BoolVal(true)),
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
@@ -208,20 +888,24 @@ impl ExprVisitor for Interpreter {
 		    (Value::IntVal(l), Value::IntVal(r)) => {
 			Ok(Value::BoolVal(l != r))
 		    },
+		    (Value::CharVal(l), Value::CharVal(r)) => {
+			Ok(Value::BoolVal(l != r))
+		    },
 		    (Value::BoolVal(l), Value::BoolVal(r)) => {
 			Ok(Value::BoolVal(l != r))
 		    },
-		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "binary expression type mismatch");
-			Err(Box::new(EvalError{}))
+		    (Value::StrVal(l), Value::StrVal(r)) => {
+			Ok(Value::BoolVal(l != r))
+		    },
+		    (l, r) => {
+			Err(mismatch(&e.operator, &l, &r))
 		    },
 		}
 	    },
 	    _ => {
 		//should be unreachable
-		println!("binary operator '{}' supported in parser but not in evaluate", e.operator.lexeme);
-		Err(Box::new(EvalError{}))
+		Err(error(ErrorKind::Internal, &e.operator,
+			  "binary operator supported in parser but not in evaluate"))
 	    },
 	}
     }
@@ -231,13 +915,14 @@ impl ExprVisitor for Interpreter {
     }
 
     fn visit_literal(&mut self, e: &expr::Literal) -> Result<Value, Box<dyn Error>> {
-	use expr::Literal;
-	match e {
-	    Literal::StrLit(s) => Ok(Value::StrVal(s.clone())),
-	    Literal::RealLit(r) => Ok(Value::RealVal(*r)),
-	    Literal::IntLit(i) => Ok(Value::IntVal(*i)),
-	    Literal::BoolLit(b) => Ok(Value::BoolVal(*b)),
-	    Literal::NilLit => Ok(Value::NilVal),
+	use expr::Lit;
+	match &e.value {
+	    Lit::StrLit(s) => Ok(Value::StrVal(s.clone())),
+	    Lit::RealLit(r) => Ok(Value::RealVal(*r)),
+	    Lit::IntLit(i) => Ok(Value::IntVal(*i)),
+	    Lit::CharLit(c) => Ok(Value::CharVal(*c)),
+	    Lit::BoolLit(b) => Ok(Value::BoolVal(*b)),
+	    Lit::NilLit => Ok(Value::NilVal),
 	    //no error possible unless the parsing is buggy
 	}
     }
@@ -249,48 +934,52 @@ impl ExprVisitor for Interpreter {
 	    TokenType::Minus => match right {
 		Value::RealVal(r) => Ok(Value::RealVal(-r)),
 		_ => {
-		    crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				  "type incompatible with operator");
-		    Err(Box::new(EvalError{}))
+		    Err(error(ErrorKind::Type, &e.operator, "type incompatible with operator"))
 		},
 	    },
 	    TokenType::Bang => match right {
 		Value::BoolVal(b) => Ok(Value::BoolVal(!b)),
 		_ => {
-		    crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				  "type incompatible with operator");
-		    Err(Box::new(EvalError{}))
+		    Err(error(ErrorKind::Type, &e.operator, "type incompatible with operator"))
 		},
 	    },
 	    _ => {
 		//should be unreachable due to parsing logic
-		println!("crazy unreachable error in visit_unary");
-		Err(Box::new(EvalError{}))
+		Err(error(ErrorKind::Internal, &e.operator, "crazy unreachable error in visit_unary"))
 	    },
 	}
     }
 
     fn visit_assignment(&mut self, e: &expr::Assignment) -> Result<Value, Box<dyn Error>> {
 	let r_value = e.val.accept(self)?;
-	let l_value;
-	l_value = (*self.cur_env).borrow().get(&e.name)?;
-	match (&l_value, &r_value) {
-	    (Value::IntVal(_), Value::IntVal(_)) |
-	    (Value::RealVal(_), Value::RealVal(_)) |
-	    (Value::StrVal(_), Value::StrVal(_)) |
-	    (Value::NilVal, Value::NilVal) => {
-		(*self.cur_env).borrow_mut().assign(&e.name, &r_value)?;
-	    },
-	    _ => {
-		println!("type mismatch in {:?} and {:?}", l_value, r_value);
-		return Err(Box::new(EvalError {}));
-	    },
-	};
+	self.assign(self.scope(e.node.id), &e.name, &r_value)?;
+	Ok(r_value)
+    }
+
+    fn visit_tuple(&mut self, e: &expr::Tuple) -> Result<Value, Box<dyn Error>> {
+	let mut items = Vec::<Value>::new();
+	for i in e.items.iter() {
+	    items.push(i.accept(self)?);
+	}
+	Ok(Value::TupleVal(Rc::new(items)))
+    }
+
+    fn visit_list(&mut self, e: &expr::List) -> Result<Value, Box<dyn Error>> {
+	let mut items = Vec::<Value>::new();
+	for i in e.items.iter() {
+	    items.push(i.accept(self)?);
+	}
+	Ok(Value::ListVal(Rc::new(items)))
+    }
+
+    fn visit_destructure(&mut self, e: &expr::Destructure) -> Result<Value, Box<dyn Error>> {
+	let r_value = e.val.accept(self)?;
+	self.bind_pattern(&e.target, r_value.clone(), false)?;
 	Ok(r_value)
     }
 
     fn visit_variable(&mut self, e: &expr::Variable) -> Result<Value, Box<dyn Error>> {
-	(*self.cur_env).borrow().get(&e.name)
+	(*self.scope(e.node.id)).borrow().get(&e.name)
     }
 
     fn visit_logical(&mut self, e: &expr::Logical) -> Result<Value, Box<dyn Error>> {
@@ -304,9 +993,7 @@ impl ExprVisitor for Interpreter {
 			}
 		    },
 		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "non boolean value in logical expression");
-			return Err(Box::new(crate::RuntimeError {}));
+			return Err(error(ErrorKind::Type, &e.operator, "non boolean value in logical expression"));
 		    },
 		}
 	    },
@@ -318,9 +1005,7 @@ impl ExprVisitor for Interpreter {
 			}
 		    },
 		    _ => {
-			crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-				      "non boolean value in logical expression");
-			return Err(Box::new(crate::RuntimeError {}));
+			return Err(error(ErrorKind::Type, &e.operator, "non boolean value in logical expression"));
 		    },
 		}
 	    },
@@ -329,179 +1014,374 @@ impl ExprVisitor for Interpreter {
 	match e.right.accept(self)? {
 	    Value::BoolVal(b) => Ok(Value::BoolVal(b)),
 	    _ => {
-		crate::report(e.operator.line, &format!(" at '{}'", e.operator.lexeme),
-			      "non boolean value in logical expression");
-		Err(Box::new(crate::RuntimeError {}))
+		Err(error(ErrorKind::Type, &e.operator, "non boolean value in logical expression"))
 	    },
 	}
     }
-}
 
-impl StmtVisitor for Interpreter {
-    //the match in Stmt::accept() makes the _ arms here
-    //redundant. might be better to have just one visit
-    //and do the matching in that, or else
-    //different stmt structs like the exprs
-    fn visit_print(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::Print(e) => {
-		let val = e.accept(self)?;
-		println!("{val}");
-		Ok(())
-	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
-	    },
+    fn visit_conditional(&mut self, e: &expr::Conditional) -> Result<Value, Box<dyn Error>> {
+	match e.cond.accept(self)? {
+	    Value::BoolVal(true) => e.then_expr.accept(self),
+	    Value::BoolVal(false) => e.else_expr.accept(self),
+	    _ => Err(error(ErrorKind::Type, &e.keyword, "conditional expression must be boolean")),
 	}
     }
 
-    fn visit_expression(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::Expression(e) => {
-		e.accept(self)?;
-		Ok(())
-	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
-	    },
+    fn visit_lambda(&mut self, e: &expr::Lambda) -> Result<Value, Box<dyn Error>> {
+	Ok(Value::FnVal(Rc::new(Function {
+	    def: e.def.clone(),
+	    links: self.links_for(&e.def, self.cur_env.clone()),
+	})))
+    }
+
+    fn visit_call(&mut self, e: &expr::Call) -> Result<Value, Box<dyn Error>> {
+	match self.prepare_call(e)? {
+	    Prepared::Call(c) => self.call_function(c),
+	    Prepared::Done(v) => Ok(v),
 	}
     }
 
-    fn visit_intdecl(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::IntDecl(n, e) => {
-		match e {
-		    Some(ex) => {
-			let v = ex.accept(self)?;
-			match v {
-			    Value::IntVal(_) => (*self.cur_env).borrow_mut().define(&n, v),
-			    _ => {
-				println!("mismatched types {} and {:?}", n, v);
-				return Err(Box::new(crate::RuntimeError {}))
-			    },
-			}
-		    },
-		    None => (*self.cur_env).borrow_mut().define(&n, Value::IntVal(0)),
+    fn visit_get(&mut self, e: &expr::Get) -> Result<Value, Box<dyn Error>> {
+	let object = e.object.accept(self)?;
+	let instance = match &object {
+	    Value::InstanceVal(i) => i,
+	    //a caught error has what it was made with and nothing else
+	    Value::ErrVal(err) => {
+		return match e.name.lexeme.as_str() {
+		    "kind" => Ok(Value::StrVal(Rc::new(err.kind.to_string()))),
+		    "msg" => Ok(Value::StrVal(Rc::new(err.msg.clone()))),
+		    "line" => Ok(Value::IntVal(err.line)),
+		    _ => Err(error(ErrorKind::Undefined, &e.name, &format!("undefined property {}", e.name.lexeme))),
 		};
-		Ok(())
 	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
+	    v => return Err(error(ErrorKind::Type, &e.name, &format!("a value of type {} has no properties", v.val_type()))),
+	};
+	//fields shadow methods
+	if let Some(v) = instance.borrow().fields.get(&e.name.lexeme) {
+	    return Ok(v.clone());
+	}
+	let method = instance.borrow().class.find_method(&e.name.lexeme);
+	match method {
+	    Some(m) => Ok(Value::FnVal(self.bind(&m, &object))),
+	    None => Err(error(ErrorKind::Undefined, &e.name, &format!("undefined property {}", e.name.lexeme))),
+	}
+    }
+
+    fn visit_set(&mut self, e: &expr::Set) -> Result<Value, Box<dyn Error>> {
+	let instance = match e.object.accept(self)? {
+	    Value::InstanceVal(i) => i,
+	    v => return Err(error(ErrorKind::Type, &e.name, &format!("a value of type {} has no fields", v.val_type()))),
+	};
+	let value = e.val.accept(self)?;
+	//a field keeps the type it was first given, like a variable
+	let old = instance.borrow().fields.get(&e.name.lexeme).map(|v| v.val_type());
+	match old {
+	    Some(t) if !value.is(&t) => {
+		return Err(error(ErrorKind::Type, &e.name,
+				 &format!("field {} is {} and can't be set to {}", e.name.lexeme, t, value.val_type())));
 	    },
+	    _ => {},
 	}
+	instance.borrow_mut().fields.insert(e.name.lexeme.clone(), value.clone());
+	Ok(value)
     }
 
-    fn visit_realdecl(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::RealDecl(n, e) => {
-		match e {
-		    Some(ex) => {
-			let v = ex.accept(self)?;
-			match v {
-			    Value::RealVal(_) => (*self.cur_env).borrow_mut().define(&n, v),
-			    _ => {
-				println!("mismatched types {} and {:?}", n, v);
-				return Err(Box::new(crate::RuntimeError {}))
-			    },
-			}
+    fn visit_self(&mut self, e: &expr::SelfExpr) -> Result<Value, Box<dyn Error>> {
+	(*self.scope(e.node.id)).borrow().get(&e.keyword)
+    }
+
+    fn visit_super(&mut self, e: &expr::Super) -> Result<Value, Box<dyn Error>> {
+	let d = *self.depths.get(&e.node.id).ok_or_else(impossible)?;
+	let superclass = match (*self.locate(&d)).borrow().get(&e.keyword)? {
+	    Value::ClassVal(c) => c,
+	    _ => return Err(impossible()),
+	};
+	//self is in the scope just inside super's
+	let at_self = Depth {
+	    scopes: d.scopes - 1,
+	    from_link: d.from_link - 1,
+	    ..d
+	};
+	let instance = (*self.locate(&at_self)).borrow().get(&Token::new(TokenType::SelfKw, "self".to_string(), e.keyword.line))?;
+	match superclass.find_method(&e.method.lexeme) {
+	    Some(m) => Ok(Value::FnVal(self.bind(&m, &instance))),
+	    None => Err(error(ErrorKind::Undefined, &e.method, &format!("undefined method {}", e.method.lexeme))),
+	}
+    }
+
+    fn visit_index(&mut self, e: &expr::Index) -> Result<Value, Box<dyn Error>> {
+	let object = e.object.accept(self)?;
+	let i = match e.index.accept(self)? {
+	    Value::IntVal(i) => i as usize,
+	    v => return Err(error(ErrorKind::Type, &e.bracket, &format!("index must be int, not {}", v.val_type()))),
+	};
+	let item = match &object {
+	    Value::ListVal(l) | Value::TupleVal(l) => l.get(i).cloned(),
+	    Value::StrVal(s) => s.chars().nth(i).map(Value::CharVal),
+	    v => return Err(error(ErrorKind::Type, &e.bracket, &format!("can't index a value of type {}", v.val_type()))),
+	};
+	match item {
+	    Some(v) => Ok(v),
+	    None => Err(error(ErrorKind::Index, &e.bracket, &format!("index {i} out of range"))),
+	}
+    }
+
+    fn visit_range(&mut self, e: &expr::Range) -> Result<Value, Box<dyn Error>> {
+	let mut bounds = Vec::<u32>::new();
+	for b in [Some(&e.start), Some(&e.end), e.step.as_ref()].into_iter().flatten() {
+	    match b.accept(self)? {
+		Value::IntVal(i) => bounds.push(i),
+		v => return Err(error(ErrorKind::Type, &e.operator,
+				      &format!("range bounds must be int, not {}", v.val_type()))),
+	    }
+	}
+	let step = bounds.get(2).copied().unwrap_or(1);
+	if step == 0 {
+	    return Err(error(ErrorKind::Argument, &e.operator, "range step can't be 0"));
+	}
+	Ok(Value::RangeVal(expr::IntRange {
+	    start: bounds[0],
+	    end: bounds[1],
+	    step,
+	    inclusive: e.operator.t_type == TokenType::DotDotEqual,
+	}))
+    }
+
+    fn visit_cast(&mut self, e: &expr::Cast) -> Result<Value, Box<dyn Error>> {
+	let val = e.expr.accept(self)?;
+	match (&e.target.t_type, val) {
+	    (TokenType::Int, Value::CharVal(c)) => Ok(Value::IntVal(c as u32)),
+	    (TokenType::Int, Value::IntVal(i)) => Ok(Value::IntVal(i)),
+	    (TokenType::Char, Value::IntVal(i)) => {
+		if self.byte_chars && i > 0xff {
+		    return Err(error(ErrorKind::Conversion, &e.target, &format!("character code {i} does not fit in a byte")));
+		}
+		match char::from_u32(i) {
+		    Some(c) => Ok(Value::CharVal(c)),
+		    None => {
+			Err(error(ErrorKind::Conversion, &e.target, &format!("{i} is not a valid character code")))
 		    },
-		    None => (*self.cur_env).borrow_mut().define(&n, Value::RealVal(0.0)),
-		};
-		Ok(())
+		}
 	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
+	    (TokenType::Char, Value::CharVal(c)) => Ok(Value::CharVal(c)),
+	    (_, v) => {
+		Err(error(ErrorKind::Conversion, &e.target, &format!("cannot convert {:?} to {}", v, e.target.lexeme)))
 	    },
 	}
     }
+}
 
-    fn visit_strdecl(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::StrDecl(n, e) => {
-		match e {
-		    Some(ex) => {
-			let v = ex.accept(self)?;
-			match v {
-			    Value::StrVal(_) => (*self.cur_env).borrow_mut().define(&n, v),
-			    _ => {
-				println!("mismatched types {} and {:?}", n, v);
-				return Err(Box::new(crate::RuntimeError {}))
-			    },
-			}
-		    },
-		    None => (*self.cur_env).borrow_mut().define(&n, expr::Value::StrVal(Rc::new(String::new()))),
-		};
-		Ok(())
-	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
+impl StmtVisitor<Result<(), Box<dyn Error>>> for Interpreter {
+    fn visit_print(&mut self, s: &stmt::Print) -> Result<(), Box<dyn Error>> {
+	let stmt::Print{expr: e, ..} = s;
+	let val = e.accept(self)?;
+	println!("{val}");
+	Ok(())
+    }
+
+    fn visit_expression(&mut self, s: &stmt::Expression) -> Result<(), Box<dyn Error>> {
+	let stmt::Expression{expr: e, ..} = s;
+	e.accept(self)?;
+	Ok(())
+    }
+
+    fn visit_typed_decl(&mut self, s: &stmt::TypedDecl) -> Result<(), Box<dyn Error>> {
+	let stmt::TypedDecl{d_type: t, name: n, init: e, ..} = s;
+	let v = match e {
+	    Some(ex) => ex.accept(self)?,
+	    None => match t {
+		ValType::Int => Value::IntVal(0),
+		ValType::Real => Value::RealVal(0.0),
+		ValType::Str => Value::StrVal(Rc::new(String::new())),
+		_ => Value::CharVal('\0'),
 	    },
+	};
+	if v.val_type() != *t {
+	    return Err(error(ErrorKind::Type, n, &format!("mismatched types {} and {:?}", n.lexeme, v)));
 	}
+	(*self.cur_env).borrow_mut().define(&n.lexeme, v);
+	Ok(())
+    }
+
+    fn visit_vardecl(&mut self, s: &stmt::VarDecl) -> Result<(), Box<dyn Error>> {
+	let stmt::VarDecl{pattern: p, init: e, ..} = s;
+	let v = e.accept(self)?;
+	self.bind_pattern(p, v, true)
     }
 
-    fn visit_block(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::Block(s) => {
-		self.exec_block(s, Rc::new(RefCell::new(Environment::new(Some(self.cur_env.clone())))))
+    fn visit_decls(&mut self, s: &stmt::Decls) -> Result<(), Box<dyn Error>> {
+	let stmt::Decls{decls: s, ..} = s;
+	for stmt in s.iter() {
+	    stmt.accept(self)?;
+	}
+	Ok(())
+    }
+
+    fn visit_fndecl(&mut self, s: &stmt::FnDecl) -> Result<(), Box<dyn Error>> {
+	let stmt::FnDecl{name: n, init: e, ..} = s;
+	match e.accept(self)? {
+	    v @ Value::FnVal(_) => (*self.cur_env).borrow_mut().define(&n.lexeme, v),
+	    v => return Err(error(ErrorKind::Type, n, &format!("mismatched types {} and {:?}", n.lexeme, v))),
+	};
+	Ok(())
+    }
+
+    fn visit_class(&mut self, s: &stmt::Class) -> Result<(), Box<dyn Error>> {
+	let stmt::Class{name: n, superclass: sup, interfaces, methods, ..} = s;
+	let superclass = match sup {
+	    Some(sup) => match sup.accept(self)? {
+		Value::ClassVal(c) => Some(c),
+		v => return Err(error(ErrorKind::Type, n,
+				      &format!("superclass must be a class, not {}", v.val_type()))),
 	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
+	    None => None,
+	};
+	//methods see super through an environment of their own
+	let closure = match &superclass {
+	    Some(c) => {
+		let env = Rc::new(RefCell::new(Environment::new(Some(self.cur_env.clone()))));
+		env.borrow_mut().define(&Rc::new("super".to_string()), Value::ClassVal(c.clone()));
+		env
 	    },
+	    None => self.cur_env.clone(),
+	};
+	let mut table = std::collections::HashMap::new();
+	for def in methods.iter() {
+	    let name = def.name.as_ref().expect("method without a name, fix parser::methods");
+	    table.insert(name.lexeme.to_string(), Rc::new(Function {
+		def: def.clone(),
+		links: self.links_for(def, closure.clone()),
+	    }));
 	}
+	let class = Class {
+	    name: n.clone(),
+	    superclass,
+	    methods: table,
+	    interfaces: interfaces.iter().map(|i| i.lexeme.clone()).collect(),
+	};
+	(*self.cur_env).borrow_mut().define(&n.lexeme, Value::ClassVal(Rc::new(class)));
+	Ok(())
     }
 
-    fn visit_if(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::If(c, t, e) => {
-		match c.accept(self)? {
-		    Value::BoolVal(b) => {
-			if b {
-			    t.accept(self)?;
-			} else {
-			    match e {
-				Some(el) => el.accept(self)?,
-				None => {},
-			    };
-			}
-			Ok(())
-		    },
-		    _ => {
-			println!("conditional expression must be boolean");
-			Err(Box::new(crate::RuntimeError {}))
-		    },
+    fn visit_interface(&mut self, _s: &stmt::Interface) -> Result<(), Box<dyn Error>> {
+	Ok(())
+    }
+
+    fn visit_block(&mut self, s: &stmt::Block) -> Result<(), Box<dyn Error>> {
+	let stmt::Block{stmts: s, ..} = s;
+	self.exec_block(s, Rc::new(RefCell::new(Environment::new(Some(self.cur_env.clone())))))
+    }
+
+    fn visit_if(&mut self, s: &stmt::If) -> Result<(), Box<dyn Error>> {
+	let stmt::If{keyword: k, cond: c, then_branch: t, else_branch: e, ..} = s;
+	match c.accept(self)? {
+	    Value::BoolVal(b) => {
+		if b {
+		    t.accept(self)?;
+		} else {
+		    if let Some(el) = e { el.accept(self)? };
 		}
+		Ok(())
 	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		Err(Box::new(crate::RuntimeError {}))
-	    },
+	    _ => Err(error(ErrorKind::Type, k, "conditional expression must be boolean")),
 	}
     }
 
-    fn visit_while(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>> {
-	match s {
-	    StmtType::While(c, s) => {
-		while match c.accept(self)? {
-		    Value::BoolVal(b) => b,
-		    _ => {
-			println!("conditional expression must be boolean");
-			return Err(Box::new(crate::RuntimeError {}));
-		    },
-		} {
-		    s.accept(self)?;
+    fn visit_while(&mut self, s: &stmt::While) -> Result<(), Box<dyn Error>> {
+	let stmt::While{keyword: k, label: l, cond: c, body: s, increment: i, ..} = s;
+	self.run_loop(k, l, Some(c.as_ref()), s, i.as_deref(), None)
+    }
+
+    fn visit_forin(&mut self, s: &stmt::ForIn) -> Result<(), Box<dyn Error>> {
+	let stmt::ForIn{name: n, label: l, iterable: e, body, ..} = s;
+	let iterable = e.accept(self)?;
+	let items = match iterable.iter() {
+	    Some(it) => it,
+	    None => return Err(error(ErrorKind::Type, n,
+				     &format!("can't loop over a value of type {}", iterable.val_type()))),
+	};
+	for item in items {
+	    let env = Rc::new(RefCell::new(Environment::new(Some(self.cur_env.clone()))));
+	    env.borrow_mut().define(&n.lexeme, item);
+	    let result = self.exec_block(body, env);
+	    if !self.loop_step(l, result)? {
+		break;
+	    }
+	}
+	Ok(())
+    }
+
+    fn visit_repeat(&mut self, s: &stmt::Repeat) -> Result<(), Box<dyn Error>> {
+	let stmt::Repeat{keyword: k, label: l, body: s, cond: c, ..} = s;
+	self.run_loop(k, l, None, s, None, Some(c.as_ref()))
+    }
+
+    fn visit_loop(&mut self, s: &stmt::Loop) -> Result<(), Box<dyn Error>> {
+	let stmt::Loop{keyword: k, label: l, body: s, ..} = s;
+	self.run_loop(k, l, None, s, None, None)
+    }
+
+    fn visit_break(&mut self, s: &stmt::Break) -> Result<(), Box<dyn Error>> {
+	let stmt::Break{keyword: k, label: l, ..} = s;
+	Err(Box::new(LoopSignal { keyword: k.clone(), label: l.clone() }))
+    }
+
+    fn visit_continue(&mut self, s: &stmt::Continue) -> Result<(), Box<dyn Error>> {
+	let stmt::Continue{keyword: k, label: l, ..} = s;
+	Err(Box::new(LoopSignal { keyword: k.clone(), label: l.clone() }))
+    }
+
+    fn visit_throw(&mut self, s: &stmt::Throw) -> Result<(), Box<dyn Error>> {
+	let stmt::Throw{keyword: k, value: e, ..} = s;
+	match e.accept(self)? {
+	    //rethrowing a caught error keeps its kind and line
+	    Value::ErrVal(err) => Err(Box::new((*err).clone())),
+	    v => Err(Box::new(RuntimeError::new(ErrorKind::User, format!("{v}"), k.line))),
+	}
+    }
+
+    fn visit_try(&mut self, s: &stmt::Try) -> Result<(), Box<dyn Error>> {
+	let stmt::Try{body, name, handler, ..} = s;
+	let env = Rc::new(RefCell::new(Environment::new(Some(self.cur_env.clone()))));
+	self.in_try += 1;
+	let result = self.exec_block(body, env);
+	self.in_try -= 1;
+	let err = match result {
+	    Ok(_) => return Ok(()),
+	    //anything that isn't a RuntimeError isn't for the script to see,
+	    //so it just keeps unwinding
+	    Err(e) => e.downcast::<RuntimeError>()?,
+	};
+	let env = Rc::new(RefCell::new(Environment::new(Some(self.cur_env.clone()))));
+	env.borrow_mut().define(&name.lexeme, Value::ErrVal(Rc::new(*err)));
+	self.exec_block(handler, env)
+    }
+
+    fn visit_assert(&mut self, s: &stmt::Assert) -> Result<(), Box<dyn Error>> {
+	let stmt::Assert{keyword: k, cond: c, message: m, ..} = s;
+	if !self.asserts {
+	    return Ok(());
+	}
+	match c.accept(self)? {
+	    Value::BoolVal(true) => Ok(()),
+	    Value::BoolVal(false) => {
+		let mut msg = format!("assertion {} failed", c.print());
+		if let Some(m) = m {
+		    msg.push_str(&format!(": {}", m.accept(self)?));
 		}
+		Err(Box::new(AssertionError::new(msg, k.line)))
 	    },
-	    _ => {
-		println!("theoretically impossible error in StmtVisitor");
-		return Err(Box::new(crate::RuntimeError {}));
-	    },
+	    _ => Err(error(ErrorKind::Type, k, "assertion must be boolean")),
 	}
-	Ok(())
+    }
+
+    fn visit_return(&mut self, s: &stmt::Return) -> Result<(), Box<dyn Error>> {
+	let stmt::Return{value: e, ..} = s;
+	let value = match e {
+	    Some(ex) if self.in_try == 0 => self.tail_eval(ex.as_ref())?,
+	    Some(ex) => ex.accept(self)?,
+	    None => Value::NilVal,
+	};
+	Err(Box::new(ReturnValue { value }))
     }
 }
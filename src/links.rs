@@ -0,0 +1,296 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+use crate::expr;
+use crate::expr::{ExprVisitor, FnDef, FnBody, Pattern, NodeId};
+use crate::resolver::Depths;
+use crate::stmt;
+use crate::stmt::{Stmt, StmtVisitor};
+
+//how many enclosing functions out each function's body uses variables
+//from, by the function's id. one that uses none isn't in it
+pub type LinkDepths = HashMap<NodeId, usize>;
+
+//works out how far out each function reaches for variables, which is
+//how many static links it needs (see "Nested functions" in spec.md)
+pub fn analyze(ast: &[Stmt], depths: &Depths) -> LinkDepths {
+    let mut l = Links {
+	depths,
+	defs: Vec::new(),
+	links: LinkDepths::new(),
+    };
+    for stmt in ast.iter() {
+	stmt.accept(&mut l);
+    }
+    l.links
+}
+
+struct Links<'a> {
+    depths: &'a Depths,
+    //the functions the code being analyzed is in, innermost last
+    defs: Vec<NodeId>,
+    links: LinkDepths,
+}
+
+impl Links<'_> {
+    //a use of something declared fns functions out, every function between
+    //here and that one needs a link that far out
+    fn reach(&mut self, id: NodeId) {
+	let fns = match self.depths.get(&id) {
+	    Some(d) => d.fns,
+	    None => return,
+	};
+	for (i, id) in self.defs.iter().rev().take(fns).enumerate() {
+	    let depth = self.links.entry(*id).or_insert(0);
+	    *depth = (*depth).max(fns - i);
+	}
+    }
+
+    fn reach_pattern(&mut self, p: &Pattern) {
+	match p {
+	    Pattern::Name(_, _, id) => self.reach(*id),
+	    Pattern::Tuple(_, items) => {
+		for i in items.iter() {
+		    self.reach_pattern(i);
+		}
+	    },
+	}
+    }
+
+    fn function(&mut self, def: &Rc<FnDef>) {
+	self.defs.push(def.id);
+	for p in def.params.iter() {
+	    if let Some(d) = &p.default {
+		d.accept(self);
+	    }
+	}
+	match &def.body {
+	    FnBody::Expr(b) => {
+		b.accept(self);
+	    },
+	    FnBody::Block(stmts) => self.visit_stmts(stmts),
+	}
+	self.defs.pop();
+    }
+
+    fn visit_stmts(&mut self, stmts: &[Stmt]) {
+	for stmt in stmts.iter() {
+	    stmt.accept(self);
+	}
+    }
+}
+
+impl ExprVisitor<()> for Links<'_> {
+    fn visit_binary(&mut self, e: &expr::Binary) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_unary(&mut self, e: &expr::Unary) {
+	e.right.accept(self)
+    }
+
+    fn visit_grouping(&mut self, e: &expr::Grouping) {
+	e.expression.accept(self)
+    }
+
+    fn visit_literal(&mut self, _e: &expr::Literal) {}
+
+    fn visit_assignment(&mut self, e: &expr::Assignment) {
+	e.val.accept(self);
+	self.reach(e.node.id);
+    }
+
+    fn visit_variable(&mut self, e: &expr::Variable) {
+	self.reach(e.node.id);
+    }
+
+    fn visit_logical(&mut self, e: &expr::Logical) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_cast(&mut self, e: &expr::Cast) {
+	e.expr.accept(self)
+    }
+
+    fn visit_conditional(&mut self, e: &expr::Conditional) {
+	e.cond.accept(self);
+	e.then_expr.accept(self);
+	e.else_expr.accept(self)
+    }
+
+    fn visit_lambda(&mut self, e: &expr::Lambda) {
+	self.function(&e.def);
+    }
+
+    fn visit_call(&mut self, e: &expr::Call) {
+	e.callee.accept(self);
+	for arg in e.args.iter() {
+	    arg.value.accept(self);
+	}
+    }
+
+    fn visit_index(&mut self, e: &expr::Index) {
+	e.object.accept(self);
+	e.index.accept(self)
+    }
+
+    fn visit_range(&mut self, e: &expr::Range) {
+	e.start.accept(self);
+	e.end.accept(self);
+	if let Some(s) = &e.step {
+	    s.accept(self);
+	}
+    }
+
+    fn visit_tuple(&mut self, e: &expr::Tuple) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_list(&mut self, e: &expr::List) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_destructure(&mut self, e: &expr::Destructure) {
+	e.val.accept(self);
+	self.reach_pattern(&e.target);
+    }
+
+    fn visit_get(&mut self, e: &expr::Get) {
+	e.object.accept(self)
+    }
+
+    fn visit_set(&mut self, e: &expr::Set) {
+	e.object.accept(self);
+	e.val.accept(self)
+    }
+
+    fn visit_self(&mut self, e: &expr::SelfExpr) {
+	self.reach(e.node.id);
+    }
+
+    //self is in the scope just inside super's, so the same link gets to both
+    fn visit_super(&mut self, e: &expr::Super) {
+	self.reach(e.node.id);
+    }
+}
+
+impl StmtVisitor<()> for Links<'_> {
+    fn visit_print(&mut self, s: &stmt::Print) {
+	let stmt::Print{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_expression(&mut self, s: &stmt::Expression) {
+	let stmt::Expression{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_typed_decl(&mut self, s: &stmt::TypedDecl) {
+	if let Some(e) = &s.init {
+	    e.accept(self);
+	}
+    }
+
+    fn visit_vardecl(&mut self, s: &stmt::VarDecl) {
+	let stmt::VarDecl{init: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_decls(&mut self, s: &stmt::Decls) {
+	let stmt::Decls{decls: s, ..} = s;
+	for stmt in s.iter() {
+	    stmt.accept(self);
+	}
+    }
+
+    fn visit_fndecl(&mut self, s: &stmt::FnDecl) {
+	let stmt::FnDecl{init: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_class(&mut self, s: &stmt::Class) {
+	let stmt::Class{superclass: sup, methods, ..} = s;
+	if let Some(sup) = sup {
+	    sup.accept(self);
+	}
+	for m in methods.iter() {
+	    self.function(m);
+	}
+    }
+
+    fn visit_interface(&mut self, _s: &stmt::Interface) {}
+
+    fn visit_block(&mut self, s: &stmt::Block) {
+	let stmt::Block{stmts: s, ..} = s;
+	self.visit_stmts(s);
+    }
+
+    fn visit_if(&mut self, s: &stmt::If) {
+	let stmt::If{cond: c, then_branch: t, else_branch: e, ..} = s;
+	c.accept(self);
+	t.accept(self);
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+    }
+
+    fn visit_while(&mut self, s: &stmt::While) {
+	let stmt::While{cond: c, body: s, increment: i, ..} = s;
+	c.accept(self);
+	s.accept(self);
+	if let Some(i) = i {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_forin(&mut self, s: &stmt::ForIn) {
+	let stmt::ForIn{iterable: e, body, ..} = s;
+	e.accept(self);
+	self.visit_stmts(body);
+    }
+
+    fn visit_repeat(&mut self, s: &stmt::Repeat) {
+	let stmt::Repeat{body: s, cond: c, ..} = s;
+	s.accept(self);
+	c.accept(self)
+    }
+
+    fn visit_loop(&mut self, s: &stmt::Loop) {
+	let stmt::Loop{body: s, ..} = s;
+	s.accept(self)
+    }
+
+    fn visit_break(&mut self, _s: &stmt::Break) {}
+
+    fn visit_continue(&mut self, _s: &stmt::Continue) {}
+
+    fn visit_throw(&mut self, s: &stmt::Throw) {
+	let stmt::Throw{value: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_try(&mut self, s: &stmt::Try) {
+	let stmt::Try{body, handler, ..} = s;
+	self.visit_stmts(body);
+	self.visit_stmts(handler);
+    }
+
+    fn visit_assert(&mut self, s: &stmt::Assert) {
+	let stmt::Assert{cond: c, message: m, ..} = s;
+	c.accept(self);
+	if let Some(m) = m {
+	    m.accept(self);
+	}
+    }
+
+    fn visit_return(&mut self, s: &stmt::Return) {
+	if let Some(e) = &s.value {
+	    e.accept(self);
+	}
+    }
+}
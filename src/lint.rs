@@ -0,0 +1,501 @@
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use crate::expr;
+use crate::expr::{Expr, ExprVisitor, FnDef, FnBody, Lit, NodeId};
+use crate::stmt;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::token::{Token, TokenType};
+use crate::resolver::Depths;
+
+//the warnings --lint gives, each can be turned off for the whole script
+//with --allow=name, or for one line with a //lint:allow name comment on
+//that line or the one before it
+pub const LINTS: [&str; 5] = ["unused", "shadow", "unreachable", "while-false", "constant-condition"];
+
+//a comma separated list of lint names from the command line
+pub fn names(list: &str) -> HashSet<&'static str> {
+    list.split(',').map(|n| {
+	match LINTS.iter().find(|l| **l == n.trim()) {
+	    Some(l) => *l,
+	    None => panic!("unknown lint '{n}', expect one of {}", LINTS.join(", ")),
+	}
+    }).collect()
+}
+
+//prints the warnings in line order, and returns how many there were
+pub fn lint(ast: &[Stmt], depths: &Depths, enabled: &HashSet<&'static str>, comments: &[(u32, String)]) -> usize {
+    let mut l = Linter::new(depths, enabled);
+    l.pragmas(comments);
+    l.scopes.push(HashMap::new());
+    l.hoist(ast);
+    l.stmts(ast);
+    l.scopes.pop();
+    l.warnings.sort_by_key(|w| w.0);
+    for (line, where_at, msg) in l.warnings.iter() {
+	println!("{line}: Warning{where_at}: {msg}");
+    }
+    l.warnings.len()
+}
+
+struct Entry {
+    name: Token,
+    used: bool,
+    //only locals are worth saying are unused, not globals or parameters
+    local: bool,
+    //false while it's only been hoisted, it only shadows anything once it's declared
+    declared: bool,
+}
+
+//the scopes are the same as the resolver's, so a variable's depth says which one it's in
+struct Linter<'a> {
+    depths: &'a Depths,
+    enabled: &'a HashSet<&'static str>,
+    //line -> the lints allowed on it
+    allowed: HashMap<u32, Vec<String>>,
+    scopes: Vec<HashMap<Rc<String>, Entry>>,
+    warnings: Vec<(u32, String, String)>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(depths: &'a Depths, enabled: &'a HashSet<&'static str>) -> Self {
+	Linter {
+	    depths,
+	    enabled,
+	    allowed: HashMap::new(),
+	    scopes: Vec::new(),
+	    warnings: Vec::new(),
+	}
+    }
+
+    fn pragmas(&mut self, comments: &[(u32, String)]) {
+	for (line, text) in comments.iter() {
+	    let names = match text.trim().strip_prefix("lint:allow") {
+		Some(n) => n,
+		None => continue,
+	    };
+	    for n in names.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+		if !LINTS.contains(&n) {
+		    self.warnings.push((*line, String::new(), format!("unknown lint '{n}' in pragma")));
+		}
+		for l in [*line, line + 1] {
+		    self.allowed.entry(l).or_default().push(n.to_string());
+		}
+	    }
+	}
+    }
+
+    fn warn(&mut self, lint: &str, line: u32, where_at: String, msg: &str) {
+	if !self.enabled.contains(lint) {
+	    return;
+	}
+	if self.allowed.get(&line).is_some_and(|a| a.iter().any(|a| a == lint)) {
+	    return;
+	}
+	self.warnings.push((line, where_at, format!("{msg} [{lint}]")));
+    }
+
+    fn warn_at(&mut self, lint: &str, at: &Token, msg: &str) {
+	self.warn(lint, at.line, format!(" at '{}'", at.lexeme), msg);
+    }
+
+    //declared later in the scope still counts, a function can use it
+    fn hoist(&mut self, stmts: &[Stmt]) {
+	let local = self.scopes.len() > 1;
+	let scope = self.scopes.last_mut().expect("no scope, fix lint::hoist");
+	for stmt in stmts.iter() {
+	    for name in stmt.declares() {
+		scope.entry(name.lexeme.clone()).or_insert(Entry {
+		    name: name.clone(),
+		    used: false,
+		    local,
+		    declared: false,
+		});
+	    }
+	}
+    }
+
+    //local is false for the names that aren't worth warning about if they're unused
+    fn declare(&mut self, name: &Token, local: bool) {
+	let outer = self.scopes[..self.scopes.len() - 1].iter().rev()
+	    .find_map(|s| s.get(&name.lexeme).filter(|e| e.declared)).map(|e| e.name.line);
+	if let Some(line) = outer {
+	    self.warn_at("shadow", name, &format!("'{}' shadows the one declared on line {line}", name.lexeme));
+	}
+	let local = local && self.scopes.len() > 1;
+	let scope = self.scopes.last_mut().expect("no scope, fix lint::declare");
+	let used = scope.get(&name.lexeme).is_some_and(|e| e.used);
+	scope.insert(name.lexeme.clone(), Entry {
+	    name: name.clone(),
+	    used,
+	    local,
+	    declared: true,
+	});
+    }
+
+    fn push(&mut self) {
+	self.scopes.push(HashMap::new());
+    }
+
+    fn pop(&mut self) {
+	let scope = self.scopes.pop().expect("no scope, fix lint::pop");
+	for e in scope.values() {
+	    if e.local && !e.used && !e.name.lexeme.starts_with('_') {
+		self.warn_at("unused", &e.name, &format!("'{}' is never used", e.name.lexeme));
+	    }
+	}
+    }
+
+    //a list of statements, anything after a return, break, continue
+    //or throw can never run
+    fn stmts(&mut self, stmts: &[Stmt]) {
+	for (i, stmt) in stmts.iter().enumerate() {
+	    if let (Some(jump), Some(next)) = (stmt.jump(), stmts.get(i + 1)) {
+		self.warn("unreachable", next.line(), String::new(), &format!("unreachable code after {jump}"));
+	    }
+	    stmt.accept(self);
+	}
+    }
+
+    fn scoped(&mut self, stmts: &[Stmt], name: Option<&Token>) {
+	self.push();
+	if let Some(n) = name {
+	    self.declare(n, false);
+	}
+	self.hoist(stmts);
+	self.stmts(stmts);
+	self.pop();
+    }
+
+    //a method's self and super are in scopes of their own
+    fn implicit(&mut self, name: &str) {
+	self.push();
+	let t = Token::new(TokenType::Ident, name.to_string(), 0);
+	self.scopes.last_mut().expect("no scope, fix lint::implicit").insert(t.lexeme.clone(), Entry {
+	    name: t,
+	    used: true,
+	    local: false,
+	    declared: true,
+	});
+    }
+
+    fn function(&mut self, def: &FnDef) {
+	self.push();
+	for p in def.params.iter() {
+	    if let Some(d) = &p.default {
+		d.accept(self);
+	    }
+	    self.declare(&p.name, false);
+	}
+	match &def.body {
+	    FnBody::Expr(b) => {
+		b.accept(self);
+	    },
+	    FnBody::Block(stmts) => {
+		self.hoist(stmts);
+		self.stmts(stmts);
+	    },
+	}
+	self.pop();
+    }
+
+    fn used(&mut self, id: NodeId, name: &Token) {
+	let i = match self.depths.get(&id) {
+	    Some(d) => self.scopes.len().checked_sub(d.scopes + 1),
+	    None => Some(0),
+	};
+	if let Some(e) = i.and_then(|i| self.scopes[i].get_mut(&name.lexeme)) {
+	    e.used = true;
+	}
+    }
+
+    fn condition(&mut self, k: &Token, c: &Expr) {
+	if let Some(b) = constant(c) {
+	    self.warn_at("constant-condition", k, &format!("condition is always {b}"));
+	}
+	c.accept(self);
+    }
+}
+
+//the value of a condition made only of literals, if it is
+fn constant(e: &Expr) -> Option<bool> {
+    match e {
+	Expr::Literal(l) => match l.value {
+	    Lit::BoolLit(b) => Some(b),
+	    _ => None,
+	},
+	Expr::Grouping(g) => constant(g.expression.as_ref()),
+	Expr::Unary(u) => match u.operator.t_type {
+	    TokenType::Bang => constant(u.right.as_ref()).map(|b| !b),
+	    _ => None,
+	},
+	Expr::Logical(l) => {
+	    let (left, right) = (constant(l.left.as_ref()), constant(l.right.as_ref()));
+	    match (l.operator.t_type.clone(), left, right) {
+		(TokenType::And, Some(false), _) => Some(false),
+		(TokenType::Or, Some(true), _) => Some(true),
+		(TokenType::And, Some(true), r) | (TokenType::Or, Some(false), r) => r,
+		_ => None,
+	    }
+	},
+	Expr::Binary(b) => match (b.left.as_ref(), b.right.as_ref()) {
+	    (Expr::Literal(left), Expr::Literal(right)) => compare(&b.operator, &left.value, &right.value),
+	    _ => None,
+	},
+	_ => None,
+    }
+}
+
+fn compare(op: &Token, left: &Lit, right: &Lit) -> Option<bool> {
+    use std::cmp::Ordering;
+    let order = match (left, right) {
+	(Lit::IntLit(l), Lit::IntLit(r)) => l.cmp(r),
+	(Lit::CharLit(l), Lit::CharLit(r)) => l.cmp(r),
+	(Lit::BoolLit(l), Lit::BoolLit(r)) => l.cmp(r),
+	(Lit::RealLit(l), Lit::RealLit(r)) => l.partial_cmp(r)?,
+	_ => return None,
+    };
+    match op.t_type {
+	TokenType::EqualEqual => Some(order == Ordering::Equal),
+	TokenType::BangEqual => Some(order != Ordering::Equal),
+	TokenType::Less => Some(order == Ordering::Less),
+	TokenType::LessEqual => Some(order != Ordering::Greater),
+	TokenType::Greater => Some(order == Ordering::Greater),
+	TokenType::GreaterEqual => Some(order != Ordering::Less),
+	_ => None,
+    }
+}
+
+impl ExprVisitor<()> for Linter<'_> {
+    fn visit_binary(&mut self, e: &expr::Binary) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_unary(&mut self, e: &expr::Unary) {
+	e.right.accept(self)
+    }
+
+    fn visit_grouping(&mut self, e: &expr::Grouping) {
+	e.expression.accept(self)
+    }
+
+    fn visit_literal(&mut self, _e: &expr::Literal) {}
+
+    //only reading a variable uses it
+    fn visit_assignment(&mut self, e: &expr::Assignment) {
+	e.val.accept(self)
+    }
+
+    fn visit_variable(&mut self, e: &expr::Variable) {
+	self.used(e.node.id, &e.name);
+    }
+
+    fn visit_logical(&mut self, e: &expr::Logical) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_cast(&mut self, e: &expr::Cast) {
+	e.expr.accept(self)
+    }
+
+    fn visit_conditional(&mut self, e: &expr::Conditional) {
+	self.condition(&e.keyword, e.cond.as_ref());
+	e.then_expr.accept(self);
+	e.else_expr.accept(self)
+    }
+
+    fn visit_lambda(&mut self, e: &expr::Lambda) {
+	self.function(&e.def);
+    }
+
+    fn visit_call(&mut self, e: &expr::Call) {
+	e.callee.accept(self);
+	for arg in e.args.iter() {
+	    arg.value.accept(self);
+	}
+    }
+
+    fn visit_index(&mut self, e: &expr::Index) {
+	e.object.accept(self);
+	e.index.accept(self)
+    }
+
+    fn visit_range(&mut self, e: &expr::Range) {
+	e.start.accept(self);
+	e.end.accept(self);
+	if let Some(s) = &e.step {
+	    s.accept(self);
+	}
+    }
+
+    fn visit_tuple(&mut self, e: &expr::Tuple) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_list(&mut self, e: &expr::List) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_destructure(&mut self, e: &expr::Destructure) {
+	e.val.accept(self)
+    }
+
+    fn visit_get(&mut self, e: &expr::Get) {
+	e.object.accept(self)
+    }
+
+    fn visit_set(&mut self, e: &expr::Set) {
+	e.object.accept(self);
+	e.val.accept(self)
+    }
+
+    fn visit_self(&mut self, _e: &expr::SelfExpr) {}
+
+    fn visit_super(&mut self, _e: &expr::Super) {}
+}
+
+impl StmtVisitor<()> for Linter<'_> {
+    fn visit_print(&mut self, s: &stmt::Print) {
+	let stmt::Print{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_expression(&mut self, s: &stmt::Expression) {
+	let stmt::Expression{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_typed_decl(&mut self, s: &stmt::TypedDecl) {
+	let stmt::TypedDecl{name: n, init: e, ..} = s;
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+	self.declare(n, true);
+    }
+
+    fn visit_vardecl(&mut self, s: &stmt::VarDecl) {
+	let stmt::VarDecl{pattern: p, init: e, ..} = s;
+	e.accept(self);
+	for n in p.names() {
+	    self.declare(n, true);
+	}
+    }
+
+    fn visit_decls(&mut self, s: &stmt::Decls) {
+	let stmt::Decls{decls: s, ..} = s;
+	for stmt in s.iter() {
+	    stmt.accept(self);
+	}
+    }
+
+    fn visit_fndecl(&mut self, s: &stmt::FnDecl) {
+	let stmt::FnDecl{name: n, init: e, ..} = s;
+	self.declare(n, true);
+	e.accept(self)
+    }
+
+    fn visit_class(&mut self, s: &stmt::Class) {
+	let stmt::Class{name: n, superclass: sup, methods, ..} = s;
+	self.declare(n, true);
+	if let Some(sup) = sup {
+	    sup.accept(self);
+	    self.implicit("super");
+	}
+	for m in methods.iter() {
+	    self.implicit("self");
+	    self.function(m);
+	    self.pop();
+	}
+	if sup.is_some() {
+	    self.pop();
+	}
+    }
+
+    fn visit_interface(&mut self, _s: &stmt::Interface) {}
+
+    fn visit_block(&mut self, s: &stmt::Block) {
+	let stmt::Block{stmts: s, ..} = s;
+	self.scoped(s, None);
+    }
+
+    fn visit_if(&mut self, s: &stmt::If) {
+	let stmt::If{keyword: k, cond: c, then_branch: t, else_branch: e, ..} = s;
+	self.condition(k, c.as_ref());
+	t.accept(self);
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+    }
+
+    //a c style for with no condition is a while true with the for keyword, which is fine
+    fn visit_while(&mut self, s: &stmt::While) {
+	let stmt::While{keyword: k, cond: c, body: s, increment: i, ..} = s;
+	match (k.t_type.clone(), constant(c.as_ref())) {
+	    (TokenType::While, Some(false)) => self.warn_at("while-false", k, "loop body never runs"),
+	    (TokenType::While, Some(true)) => {
+		self.warn_at("constant-condition", k, "condition is always true, loop ... end says so");
+	    },
+	    _ => {},
+	}
+	c.accept(self);
+	s.accept(self);
+	if let Some(i) = i {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_forin(&mut self, s: &stmt::ForIn) {
+	let stmt::ForIn{name: n, iterable: e, body, ..} = s;
+	e.accept(self);
+	self.push();
+	self.declare(n, true);
+	self.hoist(body);
+	self.stmts(body);
+	self.pop();
+    }
+
+    fn visit_repeat(&mut self, s: &stmt::Repeat) {
+	let stmt::Repeat{keyword: k, body: s, cond: c, ..} = s;
+	s.accept(self);
+	self.condition(k, c.as_ref());
+    }
+
+    fn visit_loop(&mut self, s: &stmt::Loop) {
+	let stmt::Loop{body: s, ..} = s;
+	s.accept(self)
+    }
+
+    fn visit_break(&mut self, _s: &stmt::Break) {}
+
+    fn visit_continue(&mut self, _s: &stmt::Continue) {}
+
+    fn visit_throw(&mut self, s: &stmt::Throw) {
+	let stmt::Throw{value: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_try(&mut self, s: &stmt::Try) {
+	let stmt::Try{body, name, handler, ..} = s;
+	self.scoped(body, None);
+	self.scoped(handler, Some(name));
+    }
+
+    fn visit_assert(&mut self, s: &stmt::Assert) {
+	let stmt::Assert{cond: c, message: m, ..} = s;
+	c.accept(self);
+	if let Some(m) = m {
+	    m.accept(self);
+	}
+    }
+
+    fn visit_return(&mut self, s: &stmt::Return) {
+	if let Some(e) = &s.value {
+	    e.accept(self);
+	}
+    }
+}
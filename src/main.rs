@@ -1,3 +1,4 @@
+use std::collections::HashSet;
 use std::env;
 use std::error::Error;
 
@@ -7,46 +8,127 @@ mod expr;
 mod stmt;
 mod parser;
 mod environment;
+mod links;
+mod resolver;
+mod checker;
+mod lint;
+mod definite;
+mod fold;
 mod interpreter;
 use crate::scanner::Scanner;
 use crate::interpreter::Interpreter;
 use crate::parser::Parser;
+use crate::resolver::Resolver;
+use crate::checker::Checker;
+use crate::definite::Definite;
+
+//exit statuses for run_file, so a test runner can tell a failed
+//assertion apart from a script that fell over
+const EXIT_ERROR: i32 = 1;
+const EXIT_ASSERT: i32 = 2;
+
+struct Options {
+    //restrict char to a single byte (PETSCII/ASCII)
+    byte_chars: bool,
+    asserts: bool,
+    //reading a variable that might not be assigned yet is an error
+    strict: bool,
+    //the lints to give warnings for instead of running, None to run normally
+    lints: Option<HashSet<&'static str>>,
+    allowed: HashSet<&'static str>,
+}
 
 fn main() {
-    match env::args().len() {
-	2 => run_file(env::args().nth(1).expect("No 1st argument even though the length matched 1...")),
-	1 => run_prompt(),
-	_ => panic!("Usage: interptest [path]"),
+    let mut opts = Options {
+	byte_chars: false,
+	asserts: true,
+	strict: false,
+	lints: None,
+	allowed: HashSet::new(),
+    };
+    let mut paths = Vec::<String>::new();
+    for arg in env::args().skip(1) {
+	match arg.as_str() {
+	    "--byte-chars" => opts.byte_chars = true,
+	    "--no-asserts" => opts.asserts = false,
+	    "--strict" => opts.strict = true,
+	    "--lint" => opts.lints = Some(lint::LINTS.into_iter().collect()),
+	    a if a.starts_with("--lint=") => opts.lints = Some(lint::names(&a["--lint=".len()..])),
+	    a if a.starts_with("--allow=") => opts.allowed.extend(lint::names(&a["--allow=".len()..])),
+	    _ => paths.push(arg),
+	}
+    }
+
+    match paths.len() {
+	1 => run_file(paths.remove(0), &opts),
+	0 => run_prompt(&opts),
+	_ => panic!("Usage: interptest [--byte-chars] [--no-asserts] [--strict] [--lint[=names]] [--allow=names] [path]"),
     }
 }
 
-fn run_file(path: String) {
+fn run_file(path: String, opts: &Options) {
     let mut i = Interpreter::new();
+    i.byte_chars = opts.byte_chars;
+    i.asserts = opts.asserts;
+    let mut r = Resolver::new();
+    let mut c = Checker::new();
+    let mut d = Definite::new();
     let buf: Vec<u8> = std::fs::read(path).unwrap();
-    run(String::from_utf8(buf).expect("run_file: invalid UTF-8 sequence in buf"), &mut i);
+    match run(String::from_utf8(buf).expect("run_file: invalid UTF-8 sequence in buf"), &mut r, &mut c, &mut d, &mut i, opts) {
+	Ok(_) => {},
+	Err(e) if e.is::<AssertionError>() => std::process::exit(EXIT_ASSERT),
+	Err(_) => std::process::exit(EXIT_ERROR),
+    }
 }
 
-fn run_prompt() {
+fn run_prompt(opts: &Options) {
     let mut i = Interpreter::new();
+    i.byte_chars = opts.byte_chars;
+    i.asserts = opts.asserts;
+    //kept between lines so they know the globals declared so far
+    let mut r = Resolver::new();
+    let mut c = Checker::new();
+    let mut d = Definite::new();
     loop {
 	println!("ready");
 	let mut line = String::new();
-	std::io::stdin().read_line(&mut line).unwrap();
-	run(line, &mut i);
+	//end of input, e.g. ^D or the end of a piped script
+	if std::io::stdin().read_line(&mut line).unwrap() == 0 {
+	    break;
+	}
+	//errors have already been reported, just keep going
+	let _ = run(line, &mut r, &mut c, &mut d, &mut i, opts);
     }
 }
 
-fn run(text: String, i: &mut Interpreter) -> Result<(), Box<dyn Error>> {
+fn run(text: String, r: &mut Resolver, c: &mut Checker, d: &mut Definite, i: &mut Interpreter, opts: &Options) -> Result<(), Box<dyn Error>> {
     let mut s: Scanner = Scanner::new(text);
+    s.byte_chars = opts.byte_chars;
     s.scan_tokens();
+    if s.had_error {
+	return Err(Box::new(ScanError {}));
+    }
 
-    let mut p = Parser::new(s.tokens);
-    let ast = p.parse()?;
+    //the parser goes straight away, it holds on to the methods of classes
+    let mut ast = Parser::new(s.tokens).parse()?;
+    let depths = r.resolve(&ast)?;
+    c.check(&ast, &depths)?;
+    if opts.strict {
+	d.check(&ast, &depths)?;
+    }
+    if let Some(lints) = &opts.lints {
+	let enabled = lints.difference(&opts.allowed).copied().collect();
+	lint::lint(&ast, &depths, &enabled, &s.comments);
+	return Ok(());
+    }
+    i.linked(links::analyze(&ast, &depths));
+    i.resolved(depths);
+    //after the checks, so code that never runs still has to be right
+    fold::fold(&mut ast, i);
     for stmt in ast.iter() {
 	println!("{}", stmt.print());
     }
-    i.interpret(ast);
-    Ok(())
+    i.interpret(ast)
 }
 
 fn prerror(line: u32, msg: &str) {
@@ -57,13 +139,94 @@ fn report(line: u32, where_at: &str, msg: &str) {
     println!("{line}: Error {where_at}: {msg}");
 }
 
-#[derive (Debug)]
-struct RuntimeError {}
+#[derive (Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    Type,
+    Undefined,
+    DivideByZero,
+    Overflow,
+    Conversion,
+    Index,
+    Argument,
+    Internal,
+    //anything raised with throw
+    User,
+}
+
+impl std::fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	match self {
+	    ErrorKind::Type => write!(f, "TypeError"),
+	    ErrorKind::Undefined => write!(f, "UndefinedError"),
+	    ErrorKind::DivideByZero => write!(f, "DivideByZeroError"),
+	    ErrorKind::Overflow => write!(f, "OverflowError"),
+	    ErrorKind::Conversion => write!(f, "ConversionError"),
+	    ErrorKind::Index => write!(f, "IndexError"),
+	    ErrorKind::Argument => write!(f, "ArgumentError"),
+	    ErrorKind::Internal => write!(f, "InternalError"),
+	    ErrorKind::User => write!(f, "Error"),
+	}
+    }
+}
+
+//runtime errors are values so that try/catch can hand them to the script,
+//they only get reported if nothing catches them
+#[derive (Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    pub msg: String,
+    pub line: u32,
+}
+
+impl RuntimeError {
+    pub fn new(kind: ErrorKind, msg: String, line: u32) -> Self {
+	RuntimeError {
+	    kind,
+	    msg,
+	    line,
+	}
+    }
+}
 
 impl std::fmt::Display for RuntimeError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-	write!(f, "runtime error")
+	write!(f, "{} on line {}: {}", self.kind, self.line, self.msg)
     }
 }
 
 impl Error for RuntimeError {}
+
+//deliberately separate from RuntimeError so that try/catch can't swallow it
+#[derive (Debug)]
+pub struct AssertionError {
+    pub msg: String,
+    pub line: u32,
+}
+
+impl AssertionError {
+    pub fn new(msg: String, line: u32) -> Self {
+	AssertionError {
+	    msg,
+	    line,
+	}
+    }
+}
+
+impl std::fmt::Display for AssertionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "line {}: {}", self.line, self.msg)
+    }
+}
+
+impl Error for AssertionError {}
+
+#[derive (Debug)]
+struct ScanError {}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "scanning error")
+    }
+}
+
+impl Error for ScanError {}
@@ -1,9 +1,10 @@
 use std::error::Error;
-use crate::token::Token;
+use std::rc::Rc;
+use crate::token::{Span, Token};
 use crate::token::TokenType;
 use crate::expr::*;
+use crate::stmt;
 use crate::stmt::Stmt;
-use crate::stmt::StmtType;
 
 macro_rules! type_match {
     ($val:expr, $var:path) => {
@@ -17,13 +18,30 @@ macro_rules! type_match {
 pub struct Parser {
     tokens: Vec<Token>,
     current: usize,
+    //how many function bodies deep we are, for checking return
+    fn_depth: usize,
+    //the labels of the loops we're in inside the current function,
+    //innermost last, for checking break/continue
+    loops: Vec<Option<Token>>,
+    //the classes we're in, and whether each has a superclass, for checking self and super
+    classes: Vec<bool>,
+    //the fn_depth of an init method's body, where return can't have a value
+    init_depth: Option<usize>,
+    //every interface and class declared so far, for checking implements
+    //the type parameters of the generic functions we're in
+    type_params: Vec<Rc<String>>,
 }
 
 impl Parser {
     pub fn new(tokens: Vec<Token>) -> Self {
 	Parser {
-	    tokens: tokens,
+	    tokens,
 	    current: 0,
+	    fn_depth: 0,
+	    loops: Vec::new(),
+	    classes: Vec::new(),
+	    init_depth: None,
+	    type_params: Vec::new(),
 	}
     }
 
@@ -43,6 +61,13 @@ impl Parser {
 	&self.tokens[self.current]
     }
 
+    fn peek_next(&mut self) -> &Token {
+	if self.is_at_end() {
+	    return self.peek();
+	}
+	&self.tokens[self.current + 1]
+    }
+
     fn previous(&self) -> &Token {
 	&self.tokens[self.current.saturating_sub(1)]
     }
@@ -54,27 +79,185 @@ impl Parser {
 	self.previous()
     }
 
+    //every statement in a list remembers where it starts and ends
     fn declaration(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let start = self.peek().span();
+	self.decl_or_stmt().map(|s| s.at(self.since(start)))
+    }
+
+    //from start to the end of the last token read
+    fn since(&self, start: Span) -> Span {
+	start.to(self.previous().span())
+    }
+
+    //a block the parser wraps around statements it read itself, like a
+    //loop body, spanning what it read
+    fn block_stmt(&self, start: Span, stmts: Vec<Stmt>) -> Stmt {
+	Stmt::Block(stmt::Block::new(stmts)).at(self.since(start))
+    }
+
+    fn decl_or_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	//int(x) and char(x) are conversions, not declarations
+	if matches!(self.peek().t_type, TokenType::Int | TokenType::Char) && self.peek_next().t_type == TokenType::LParen {
+	    return self.statement();
+	}
 	match self.peek().t_type {
-	    TokenType::Int => {
+	    TokenType::Int | TokenType::Real | TokenType::Str | TokenType::Char => {
+		self.typed_decl()
+	    },
+	    TokenType::Fn => {
+		self.advance();
+		self.fn_decl()
+	    },
+	    TokenType::Function => {
+		self.advance();
+		self.function_decl()
+	    },
+	    TokenType::Var => {
 		self.advance();
-		self.int_decl()
+		self.var_decl()
 	    },
-	    TokenType::Real => {
+	    TokenType::Class => {
 		self.advance();
-		self.real_decl()
+		self.class_decl()
 	    },
-	    TokenType::Str => {
+	    TokenType::Interface => {
 		self.advance();
-		self.str_decl()
+		self.interface_decl()
 	    },
 	    _ => self.statement(),
 	}
     }
 
-    fn int_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+    //int a, b = 2, c; is three declarations in one statement
+    fn typed_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let d_type = match self.advance().t_type {
+	    TokenType::Int => ValType::Int,
+	    TokenType::Real => ValType::Real,
+	    TokenType::Str => ValType::Str,
+	    _ => ValType::Char,
+	};
+	let mut decls = Vec::<Stmt>::new();
+	loop {
+	    let start = self.peek().span();
+	    let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+		Ok(t) => t.clone(),
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect variable name");
+		    return Err(e)
+		},
+	    };
+
+	    let initializer: Option<Box<Expr>> = match self.peek().t_type {
+		TokenType::Equal => {
+		    self.advance();
+		    Some(self.expression()?)
+		},
+		_ => None,
+	    };
+	    decls.push(Stmt::TypedDecl(stmt::TypedDecl::new(d_type.clone(), name, initializer)).at(self.since(start)));
+
+	    match self.peek().t_type {
+		TokenType::Comma => {
+		    self.advance();
+		},
+		_ => break,
+	    }
+	}
+
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ';' after declaration");
+		return Err(e)
+	    },
+	};
+
+	match decls.len() {
+	    1 => Ok(decls.pop().unwrap()),
+	    _ => Ok(Stmt::Decls(stmt::Decls::new(decls))),
+	}
+    }
+
+    //var x = e; var (a, b) = e; with the types coming from the values
+    fn var_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let mut decls = Vec::<Stmt>::new();
+	loop {
+	    let start = self.peek().span();
+	    let target = self.pattern()?;
+	    match self.consume(|t_type| type_match!(t_type, TokenType::Equal)) {
+		Ok(_) => {},
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "var declaration must be initialized");
+		    return Err(e)
+		},
+	    };
+	    let initializer = self.expression()?;
+	    decls.push(Stmt::VarDecl(stmt::VarDecl::new(target, initializer)).at(self.since(start)));
+
+	    match self.peek().t_type {
+		TokenType::Comma => {
+		    self.advance();
+		},
+		_ => break,
+	    }
+	}
+
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ';' after declaration");
+		return Err(e)
+	    },
+	};
+
+	match decls.len() {
+	    1 => Ok(decls.pop().unwrap()),
+	    _ => Ok(Stmt::Decls(stmt::Decls::new(decls))),
+	}
+    }
+
+    //a name, optionally typed, or a parenthesized list of patterns
+    fn pattern(&mut self) -> Result<Pattern, Box<dyn Error>> {
+	if self.peek().t_type == TokenType::LParen {
+	    let paren = self.advance().clone();
+	    let mut items = vec![self.pattern()?];
+	    while self.peek().t_type == TokenType::Comma {
+		self.advance();
+		items.push(self.pattern()?);
+	    }
+	    match self.consume(|t_type| type_match!(t_type, TokenType::RParen)) {
+		Ok(_) => {},
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "missing ')' after names");
+		    return Err(e)
+		},
+	    };
+	    if items.len() < 2 {
+		crate::report(paren.line, &format!(" at '{}'", paren.lexeme),
+			      "need at least two names to destructure");
+		return Err(Box::new(ParseError{}));
+	    }
+	    return Ok(Pattern::Tuple(paren, items));
+	}
+	let p_type = self.type_name()?;
+	match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+	    Ok(t) => Ok(Pattern::Name(p_type, t.clone(), NodeId::next())),
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect variable name");
+		Err(e)
+	    },
+	}
+    }
+    fn fn_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
 	let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
-	    Ok(t) => t.lexeme.clone(),
+	    Ok(t) => t.clone(),
 	    Err(e) => {
 		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
 			      "expect variable name");
@@ -82,13 +265,15 @@ impl Parser {
 	    },
 	};
 
-	let initializer: Option<Box<dyn Expr>> = match self.peek().t_type {
-	    TokenType::Equal => {
-		self.advance();
-		Some(self.expression()?)
+	match self.consume(|t_type| type_match!(t_type, TokenType::Equal)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "fn variable must be initialized");
+		return Err(e)
 	    },
-	    _ => None,
 	};
+	let initializer = self.expression()?;
 
 	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
 	    Ok(_) => {},
@@ -99,70 +284,500 @@ impl Parser {
 	    },
 	};
 
-	Ok(Stmt::new(StmtType::IntDecl(name, initializer)))
+	Ok(Stmt::FnDecl(stmt::FnDecl::new(name, initializer)))
     }
 
-    fn real_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+    //function name(...) ... end is just a fn declaration
+    //initialized with an anonymous function
+    fn function_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
 	let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
-	    Ok(t) => t.lexeme.clone(),
+	    Ok(t) => t.clone(),
 	    Err(e) => {
 		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
-			      "expect variable name");
+			      "expect function name");
 		return Err(e)
 	    },
 	};
+	let def = self.function(keyword.clone(), Some(name.clone()))?;
+	let span = keyword.span().to(self.previous().span());
+	Ok(Stmt::FnDecl(stmt::FnDecl::new(name, Box::new(Expr::Lambda(Lambda::new(span, def))))))
+    }
 
-	let initializer: Option<Box<dyn Expr>> = match self.peek().t_type {
-	    TokenType::Equal => {
+    fn class_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+	    Ok(t) => t.clone(),
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect class name");
+		return Err(e)
+	    },
+	};
+	let superclass: Option<Box<Expr>> = match self.peek().t_type {
+	    TokenType::Less => {
 		self.advance();
-		Some(self.expression()?)
+		let s = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+		    Ok(t) => t.clone(),
+		    Err(e) => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect superclass name");
+			return Err(e)
+		    },
+		};
+		if s.lexeme == name.lexeme {
+		    crate::report(s.line, &format!(" at '{}'", s.lexeme),
+				  "a class can't inherit from itself");
+		    return Err(Box::new(ParseError{}));
+		}
+		Some(Box::new(Expr::Variable(Variable::new(s))))
 	    },
 	    _ => None,
 	};
-
-	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	let mut interfaces = Vec::<Token>::new();
+	if self.peek().t_type == TokenType::Implements {
+	    self.advance();
+	    loop {
+		let i = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+		    Ok(t) => t.clone(),
+		    Err(e) => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect interface name");
+			return Err(e)
+		    },
+		};
+		interfaces.push(i);
+		if self.peek().t_type != TokenType::Comma {
+		    break;
+		}
+		self.advance();
+	    }
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::Begin)) {
 	    Ok(_) => {},
 	    Err(e) => {
 		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
-			      "expect ';' after declaration");
+			      "expect 'begin' before class body");
 		return Err(e)
 	    },
 	};
 
-	Ok(Stmt::new(StmtType::RealDecl(name, initializer)))
+	self.classes.push(superclass.is_some());
+	let methods = self.methods();
+	self.classes.pop();
+	let methods = methods?;
+	Ok(Stmt::Class(stmt::Class::new(name, superclass, interfaces, methods)))
     }
 
-    fn str_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
+    //a method's name, or 'operator' and the operator the method defines
+    fn method_name(&mut self) -> Result<Token, Box<dyn Error>> {
+	if self.peek().t_type == TokenType::Operator {
+	    self.advance();
+	    return match self.peek().t_type {
+		TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash |
+		TokenType::EqualEqual | TokenType::BangEqual | TokenType::Less |
+		TokenType::LessEqual | TokenType::Greater | TokenType::GreaterEqual => {
+		    Ok(self.advance().clone())
+		},
+		_ => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect an operator that can be overloaded");
+		    Err(Box::new(ParseError{}))
+		},
+	    };
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+	    Ok(t) => Ok(t.clone()),
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect method name");
+		Err(e)
+	    },
+	}
+    }
+
+    //interface Name begin function m(...) -> type; ... end
+    fn interface_decl(&mut self) -> Result<Stmt, Box<dyn Error>> {
 	let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
-	    Ok(t) => t.lexeme.clone(),
+	    Ok(t) => t.clone(),
 	    Err(e) => {
 		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
-			      "expect variable name");
+			      "expect interface name");
+		return Err(e)
+	    },
+	};
+	match self.consume(|t_type| type_match!(t_type, TokenType::Begin)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect 'begin' before interface body");
+		return Err(e)
+	    },
+	};
+
+	let mut sigs = Vec::<Rc<MethodSig>>::new();
+	while !self.is_at_end() && self.peek().t_type != TokenType::End {
+	    match self.consume(|t_type| type_match!(t_type, TokenType::Function)) {
+		Ok(_) => {},
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect method declaration");
+		    return Err(e)
+		},
+	    };
+	    let m_name = self.method_name()?;
+	    if sigs.iter().any(|s| s.name.lexeme == m_name.lexeme) {
+		crate::report(m_name.line, &format!(" at '{}'", m_name.lexeme),
+			      "duplicate method name");
+		return Err(Box::new(ParseError{}));
+	    }
+	    let params = self.params()?;
+	    //a default belongs to an implementation, not the interface
+	    if let Some(p) = params.iter().find(|p| p.default.is_some()) {
+		crate::report(p.name.line, &format!(" at '{}'", p.name.lexeme),
+			      "interface method parameter can't have a default");
+		return Err(Box::new(ParseError{}));
+	    }
+	    let ret = self.return_type()?;
+	    check_operator(&m_name, &params, &ret)?;
+	    match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+		Ok(_) => {},
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect ';' after interface method");
+		    return Err(e)
+		},
+	    };
+	    sigs.push(Rc::new(MethodSig {
+		name: m_name,
+		params,
+		ret,
+	    }));
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::End)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "missing 'end' to interface");
+		return Err(e)
+	    },
+	};
+
+	Ok(Stmt::Interface(stmt::Interface::new(name, sigs)))
+    }
+
+    //the body of a class, up to and including its end
+    fn methods(&mut self) -> Result<Vec<Rc<FnDef>>, Box<dyn Error>> {
+	let mut methods = Vec::<Rc<FnDef>>::new();
+	while !self.is_at_end() && self.peek().t_type != TokenType::End {
+	    let keyword = match self.consume(|t_type| type_match!(t_type, TokenType::Function)) {
+		Ok(t) => t.clone(),
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect method declaration");
+		    return Err(e)
+		},
+	    };
+	    let name = self.method_name()?;
+	    if methods.iter().any(|m| m.name.as_ref().is_some_and(|n| n.lexeme == name.lexeme)) {
+		crate::report(name.line, &format!(" at '{}'", name.lexeme),
+			      "duplicate method name");
+		return Err(Box::new(ParseError{}));
+	    }
+	    let is_init = *name.lexeme == "init";
+	    let init_depth = self.init_depth;
+	    self.init_depth = match is_init {
+		true => Some(self.fn_depth + 1),
+		false => None,
+	    };
+	    let def = self.function(keyword, Some(name.clone()));
+	    self.init_depth = init_depth;
+	    let def = def?;
+	    if is_init && def.ret.is_some() {
+		crate::report(name.line, &format!(" at '{}'", name.lexeme),
+			      "init can't declare a return type");
+		return Err(Box::new(ParseError{}));
+	    }
+	    check_operator(&name, &def.params, &def.ret)?;
+	    methods.push(Rc::new(def));
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::End)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "missing 'end' to class");
+		return Err(e)
+	    },
+	};
+	Ok(methods)
+    }
+
+    //a parameter list, parentheses included
+    fn params(&mut self) -> Result<Vec<Param>, Box<dyn Error>> {
+	match self.consume(|t_type| type_match!(t_type, TokenType::LParen)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect '(' to start parameter list");
 		return Err(e)
 	    },
 	};
 
-	let initializer: Option<Box<dyn Expr>> = match self.peek().t_type {
-	    TokenType::Equal => {
+	let mut params = Vec::<Param>::new();
+	if self.peek().t_type != TokenType::RParen {
+	    loop {
+		if params.last().is_some_and(|p: &Param| p.variadic) {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "variadic parameter must be the last one");
+		    return Err(Box::new(ParseError{}));
+		}
+		let by_ref = match self.peek().t_type {
+		    TokenType::Var => {
+			self.advance();
+			true
+		    },
+		    _ => false,
+		};
+		let p_type = match self.type_name()? {
+		    Some(t) => t,
+		    None => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect parameter type");
+			return Err(Box::new(ParseError{}));
+		    },
+		};
+		let variadic = match self.peek().t_type {
+		    TokenType::Ellipsis if by_ref => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "var parameter can't be variadic");
+			return Err(Box::new(ParseError{}));
+		    },
+		    TokenType::Ellipsis => {
+			self.advance();
+			true
+		    },
+		    _ => false,
+		};
+		let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+		    Ok(t) => t.clone(),
+		    Err(e) => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect parameter name");
+			return Err(e)
+		    },
+		};
+		if params.iter().any(|p| p.name.lexeme == name.lexeme) {
+		    crate::report(name.line, &format!(" at '{}'", name.lexeme),
+				  "duplicate parameter name");
+		    return Err(Box::new(ParseError{}));
+		}
+		let default = match self.peek().t_type {
+		    TokenType::Equal if variadic => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "variadic parameter can't have a default");
+			return Err(Box::new(ParseError{}));
+		    },
+		    TokenType::Equal if by_ref => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "var parameter can't have a default");
+			return Err(Box::new(ParseError{}));
+		    },
+		    TokenType::Equal => {
+			self.advance();
+			Some(self.expression()?)
+		    },
+		    _ => None,
+		};
+		//defaults fill in from the right, so a required parameter
+		//after a defaulted one could never be left out
+		if default.is_none() && !variadic &&
+		    params.last().is_some_and(|p: &Param| p.default.is_some()) {
+		    crate::report(name.line, &format!(" at '{}'", name.lexeme),
+				  "parameter without a default after one with a default");
+		    return Err(Box::new(ParseError{}));
+		}
+		params.push(Param {
+		    p_type,
+		    name,
+		    default,
+		    variadic,
+		    by_ref,
+		});
+		if self.peek().t_type != TokenType::Comma {
+		    break;
+		}
 		self.advance();
-		Some(self.expression()?)
+	    }
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::RParen)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ')' after parameters");
+		return Err(e)
 	    },
-	    _ => None,
 	};
+	Ok(params)
+    }
 
-	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+    //the optional -> type after a parameter list
+    fn return_type(&mut self) -> Result<Option<ValType>, Box<dyn Error>> {
+	match self.peek().t_type {
+	    TokenType::Arrow => {
+		self.advance();
+		Ok(Some(self.any_type("expect return type after '->'")?))
+	    },
+	    _ => Ok(None),
+	}
+    }
+
+    //the optional <T, U: Bound> after a function's name
+    fn generics(&mut self) -> Result<Vec<TypeParam>, Box<dyn Error>> {
+	let mut type_params = Vec::<TypeParam>::new();
+	if self.peek().t_type != TokenType::Less {
+	    return Ok(type_params);
+	}
+	self.advance();
+	loop {
+	    let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+		Ok(t) => t.clone(),
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect type parameter name");
+		    return Err(e)
+		},
+	    };
+	    if type_params.iter().any(|t| t.name.lexeme == name.lexeme) {
+		crate::report(name.line, &format!(" at '{}'", name.lexeme),
+			      "duplicate type parameter name");
+		return Err(Box::new(ParseError{}));
+	    }
+	    let bound = match self.peek().t_type {
+		TokenType::Colon => {
+		    self.advance();
+		    Some(self.any_type("expect a type to bound the type parameter by")?)
+		},
+		_ => None,
+	    };
+	    type_params.push(TypeParam {
+		name,
+		bound,
+	    });
+	    if self.peek().t_type != TokenType::Comma {
+		break;
+	    }
+	    self.advance();
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::Greater)) {
 	    Ok(_) => {},
 	    Err(e) => {
 		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
-			      "expect ';' after declaration");
+			      "expect '>' after type parameters");
 		return Err(e)
 	    },
 	};
+	Ok(type_params)
+    }
+
+    //everything after 'fn' or 'function' (and the name, if there is one)
+    fn function(&mut self, keyword: Token, name: Option<Token>) -> Result<FnDef, Box<dyn Error>> {
+	let type_params = self.generics()?;
+	//in scope for the signature and the whole body, nested functions included
+	let outer = self.type_params.len();
+	self.type_params.extend(type_params.iter().map(|t| t.name.lexeme.clone()));
+	let params = self.params()?;
+	let ret = self.return_type()?;
+
+	//a break in a function body can't reach a loop outside of it
+	let loops = std::mem::take(&mut self.loops);
+	self.fn_depth += 1;
+	let body = match keyword.t_type {
+	    TokenType::Fn => {
+		match self.consume(|t_type| type_match!(t_type, TokenType::Colon)) {
+		    Ok(_) => {},
+		    Err(e) => {
+			self.fn_depth -= 1;
+			self.loops = loops;
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect ':' before fn body");
+			return Err(e)
+		    },
+		};
+		self.expression().map(FnBody::Expr)
+	    },
+	    _ => self.block().map(FnBody::Block),
+	};
+	self.fn_depth -= 1;
+	self.loops = loops;
+	self.type_params.truncate(outer);
+
+	Ok(FnDef {
+	    name,
+	    keyword,
+	    type_params,
+	    params,
+	    ret,
+	    body: body?,
+	    id: NodeId::next(),
+	})
+    }
 
-	Ok(Stmt::new(StmtType::StrDecl(name, initializer)))
+    fn type_name(&mut self) -> Result<Option<ValType>, Box<dyn Error>> {
+	let next_is_ident = self.peek_next().t_type == TokenType::Ident;
+	let lexeme = self.peek().lexeme.clone();
+	let is_param = self.type_params.contains(&lexeme);
+	let t = match self.peek().t_type {
+	    TokenType::Int => ValType::Int,
+	    TokenType::Real => ValType::Real,
+	    TokenType::Str => ValType::Str,
+	    TokenType::Char => ValType::Char,
+	    TokenType::Bool => ValType::Bool,
+	    TokenType::Fn => ValType::Fn,
+	    TokenType::Class => ValType::Class,
+	    TokenType::Tuple => ValType::Tuple,
+	    TokenType::List => {
+		self.advance();
+		if self.peek().t_type != TokenType::Less {
+		    return Ok(Some(ValType::List(None)));
+		}
+		self.advance();
+		let item = self.any_type("expect element type after 'list<'")?;
+		match self.consume(|t_type| type_match!(t_type, TokenType::Greater)) {
+		    Ok(_) => {},
+		    Err(e) => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect '>' after element type");
+			return Err(e)
+		    },
+		};
+		return Ok(Some(ValType::List(Some(Box::new(item)))));
+	    },
+	    TokenType::Ident if is_param => ValType::Param(lexeme),
+	    //a class name, as long as a variable name comes after it
+	    TokenType::Ident if next_is_ident => ValType::Object(lexeme),
+	    _ => return Ok(None),
+	};
+	self.advance();
+	Ok(Some(t))
+    }
+
+    //a type where a bare class name can't be mistaken for anything else, e.g. after '->'
+    fn any_type(&mut self, msg: &str) -> Result<ValType, Box<dyn Error>> {
+	match self.type_name()? {
+	    Some(t) => Ok(t),
+	    None if self.peek().t_type == TokenType::Ident => {
+		Ok(ValType::Object(self.advance().lexeme.clone()))
+	    },
+	    None => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme), msg);
+		Err(Box::new(ParseError{}))
+	    },
+	}
     }
 
     fn statement(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	if self.peek().t_type == TokenType::Ident && self.peek_next().t_type == TokenType::Colon {
+	    return self.labeled_stmt();
+	}
 	match self.peek().t_type {
 	    TokenType::Print => {
 		self.advance();
@@ -170,19 +785,47 @@ impl Parser {
 	    },
 	    TokenType::Begin => {
 		self.advance();
-		Ok(Stmt::new(StmtType::Block(self.block()?)))
+		Ok(Stmt::Block(stmt::Block::new(self.block()?)))
 	    },
 	    TokenType::If => {
 		self.advance();
-		Ok(Stmt::new(self.if_stmt()?))
+		Ok(self.if_stmt()?)
 	    },
 	    TokenType::While => {
 		self.advance();
-		Ok(Stmt::new(self.while_stmt()?))
+		Ok(self.while_stmt(None)?)
 	    },
 	    TokenType::For => {
 		self.advance();
-		Ok(Stmt::new(self.for_stmt()?))
+		Ok(self.for_stmt(None)?)
+	    },
+	    TokenType::Repeat => {
+		self.advance();
+		Ok(self.repeat_stmt(None)?)
+	    },
+	    TokenType::Loop => {
+		self.advance();
+		Ok(self.loop_stmt(None)?)
+	    },
+	    TokenType::Break | TokenType::Continue => {
+		self.advance();
+		Ok(self.break_stmt()?)
+	    },
+	    TokenType::Try => {
+		self.advance();
+		Ok(self.try_stmt()?)
+	    },
+	    TokenType::Throw => {
+		self.advance();
+		Ok(self.throw_stmt()?)
+	    },
+	    TokenType::Assert => {
+		self.advance();
+		Ok(self.assert_stmt()?)
+	    },
+	    TokenType::Return => {
+		self.advance();
+		Ok(self.return_stmt()?)
 	    },
 	    _ => self.expr_stmt(),
 	}
@@ -220,11 +863,14 @@ impl Parser {
 		return Err(e)
 	    },
 	};
-	Ok(Stmt::new(StmtType::Print(value)))
+	Ok(Stmt::Print(stmt::Print::new(value)))
     }
 
     fn expr_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
-	let expr = self.expression()?;
+	let mut expr = self.expression()?;
+	if self.peek().t_type == TokenType::Comma {
+	    expr = self.tuple_assignment(*expr)?;
+	}
 	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
 	    Ok(_) => {},
 	    Err(e) => {
@@ -233,10 +879,52 @@ impl Parser {
 		return Err(e)
 	    },
 	};
-	Ok(Stmt::new(StmtType::Expression(expr)))
+	Ok(Stmt::Expression(stmt::Expression::new(expr)))
+    }
+
+    //a, b = b, a; is (a, b) = (b, a); without the parentheses, the
+    //first comma stands in for the '(' in errors
+    fn tuple_assignment(&mut self, first: Expr) -> Result<Box<Expr>, Box<dyn Error>> {
+	let comma = self.peek().clone();
+	let targets = self.bare_tuple(first, &comma)?;
+	match self.consume(|t_type| type_match!(t_type, TokenType::Equal)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect '=' after names to assign");
+		return Err(e)
+	    },
+	};
+	let value = self.conditional()?;
+	let value = match self.peek().t_type {
+	    TokenType::Comma => {
+		let comma = self.peek().clone();
+		self.bare_tuple(*value, &comma)?
+	    },
+	    _ => value,
+	};
+	match to_pattern(targets.as_ref()) {
+	    Some(target) => Ok(Box::new(Expr::Destructure(Destructure::new(targets.span(), target, value)))),
+	    None => {
+		crate::report(comma.line, &format!(" at '{}'", comma.lexeme), "invalid l-value");
+		Err(Box::new(ParseError{}))
+	    },
+	}
     }
 
-    fn if_stmt(&mut self) -> Result<StmtType, Box<dyn Error>> {
+    //the rest of a tuple written without parentheses, after its first item
+    fn bare_tuple(&mut self, first: Expr, comma: &Token) -> Result<Box<Expr>, Box<dyn Error>> {
+	let span = first.span();
+	let mut items = vec![first];
+	while self.peek().t_type == TokenType::Comma {
+	    self.advance();
+	    items.push(*self.conditional()?);
+	}
+	Ok(Box::new(Expr::Tuple(Tuple::new(self.since(span), comma.clone(), items))))
+    }
+
+    fn if_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
 	let cond = self.expression()?;
 	match self.consume(|t_type| type_match!(t_type, TokenType::Then)) {
 	    Ok(_) => {},
@@ -247,18 +935,57 @@ impl Parser {
 	    },
 	};
 
-	let then_stmt = Stmt::new(StmtType::Block(self.block()?));
+	let start = self.peek().span();
+	let then_stmt = self.block()?;
+	let then_stmt = self.block_stmt(start, then_stmt);
 	let else_stmt = match self.peek().t_type {
 	    TokenType::Else => {
-		self.advance();
-		Some(Box::new(Stmt::new(StmtType::Block(self.block()?))))
+		let start = self.advance().span();
+		let else_stmt = self.block()?;
+		Some(Box::new(self.block_stmt(start, else_stmt)))
 	    },
 	    _ => None,
 	};
-	Ok(StmtType::If(cond, Box::new(then_stmt), else_stmt))
+	Ok(Stmt::If(stmt::If::new(keyword, cond, Box::new(then_stmt), else_stmt)))
+    }
+
+    //label: while ... and the like
+    fn labeled_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let label = self.advance().clone();
+	self.advance(); //:
+	if self.loops.iter().flatten().any(|l| l.lexeme == label.lexeme) {
+	    crate::report(label.line, &format!(" at '{}'", label.lexeme),
+			  "label already used by an enclosing loop");
+	    return Err(Box::new(ParseError{}));
+	}
+	let label = Some(label);
+	match self.peek().t_type {
+	    TokenType::While => {
+		self.advance();
+		self.while_stmt(label)
+	    },
+	    TokenType::For => {
+		self.advance();
+		self.for_stmt(label)
+	    },
+	    TokenType::Repeat => {
+		self.advance();
+		self.repeat_stmt(label)
+	    },
+	    TokenType::Loop => {
+		self.advance();
+		self.loop_stmt(label)
+	    },
+	    _ => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect a loop after label");
+		Err(Box::new(ParseError{}))
+	    },
+	}
     }
 
-    fn while_stmt(&mut self) -> Result<StmtType, Box<dyn Error>> {
+    fn while_stmt(&mut self, label: Option<Token>) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
 	let cond = self.expression()?;
 	match self.consume(|t_type| type_match!(t_type, TokenType::Do)) {
 	    Ok(_) => {},
@@ -268,19 +995,114 @@ impl Parser {
 		return Err(e)
 	    },
 	};
-	let body = Stmt::new(StmtType::Block(self.block()?));
-	Ok(StmtType::While(cond, Box::new(body)))
+	let start = self.peek().span();
+	let body = self.loop_body(&label)?;
+	let body = self.block_stmt(start, body);
+	Ok(Stmt::While(stmt::While::new(keyword, label, cond, Box::new(body), None)))
+    }
+
+    fn repeat_stmt(&mut self, label: Option<Token>) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	let start = self.peek().span();
+	let mut body = Vec::<Stmt>::new();
+	self.loops.push(label.clone());
+	while !self.is_at_end() && self.peek().t_type != TokenType::Until {
+	    match self.declaration() {
+		Ok(s) => body.push(s),
+		Err(e) => {
+		    self.loops.pop();
+		    return Err(e)
+		},
+	    }
+	}
+	self.loops.pop();
+	let body = self.block_stmt(start, body);
+	match self.consume(|t_type| type_match!(t_type, TokenType::Until)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "missing 'until' after repeat body");
+		return Err(e)
+	    },
+	};
+	let cond = self.expression()?;
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ';' after until condition");
+		return Err(e)
+	    },
+	};
+	Ok(Stmt::Repeat(stmt::Repeat::new(keyword, label, Box::new(body), cond)))
+    }
+
+    fn loop_stmt(&mut self, label: Option<Token>) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	let start = self.peek().span();
+	let body = self.loop_body(&label)?;
+	let body = self.block_stmt(start, body);
+	Ok(Stmt::Loop(stmt::Loop::new(keyword, label, Box::new(body))))
+    }
+
+    fn break_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	if self.loops.is_empty() {
+	    crate::report(keyword.line, &format!(" at '{}'", keyword.lexeme),
+			  &format!("{} outside of a loop", keyword.lexeme));
+	    return Err(Box::new(ParseError{}));
+	}
+	let label = match self.peek().t_type {
+	    TokenType::Ident => {
+		let label = self.advance().clone();
+		if !self.loops.iter().flatten().any(|l| l.lexeme == label.lexeme) {
+		    crate::report(label.line, &format!(" at '{}'", label.lexeme),
+				  &format!("no enclosing loop labeled '{}'", label.lexeme));
+		    return Err(Box::new(ParseError{}));
+		}
+		Some(label)
+	    },
+	    _ => None,
+	};
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      &format!("expect ';' after {}", keyword.lexeme));
+		return Err(e)
+	    },
+	};
+	match keyword.t_type {
+	    TokenType::Break => Ok(Stmt::Break(stmt::Break::new(keyword, label))),
+	    _ => Ok(Stmt::Continue(stmt::Continue::new(keyword, label))),
+	}
+    }
+
+    //a block that break and continue can be used in
+    fn loop_body(&mut self, label: &Option<Token>) -> Result<Vec<Stmt>, Box<dyn Error>> {
+	self.loops.push(label.clone());
+	let body = self.block();
+	self.loops.pop();
+	body
     }
 
-    fn for_stmt(&mut self) -> Result<StmtType, Box<dyn Error>> {
+    fn for_stmt(&mut self, label: Option<Token>) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	if self.peek().t_type == TokenType::Ident && self.peek_next().t_type == TokenType::In {
+	    return self.for_in_stmt(label);
+	}
 	let initializer = match self.peek().t_type {
 	    TokenType::Semicolon => {
 		self.advance();
 		None
 	    },
 	    TokenType::Int | TokenType::Real |
-	    TokenType::Str => Some(self.declaration()?),
-	    _ => Some(self.expr_stmt()?),
+	    TokenType::Str | TokenType::Char |
+	    TokenType::Var => Some(self.declaration()?),
+	    _ => {
+		let start = self.peek().span();
+		Some(self.expr_stmt()?.at(self.since(start)))
+	    },
 	};
 	
 	let condition = if !(self.peek().t_type == TokenType::Semicolon) {
@@ -311,47 +1133,160 @@ impl Parser {
 	    },
 	};
 
-	let mut body = self.block()?;
-	match increment {
-	    Some(e) => body.push(Stmt::new(StmtType::Expression(e))),
-	    None => {},
-	};
-	let body = Stmt::new(StmtType::Block(body));
+	let start = self.peek().span();
+	let body = self.loop_body(&label)?;
+	let body = self.block_stmt(start, body);
 
 	let condition = match condition {
 	    Some(e) => e,
-	    None => Box::new(Literal::BoolLit(true)),
+	    None => Box::new(Expr::Literal(Literal::new(keyword.span(), Lit::BoolLit(true)))),
 	};
 
-	let body = StmtType::While(condition, Box::new(body));
+	//the block round the loop and its initializer is the statement itself,
+	//declaration gives that its span
+	let span = self.since(keyword.span());
+	let body = Stmt::While(stmt::While::new(keyword, label, condition, Box::new(body), increment)).at(span);
 	let body = match initializer {
-	    Some(s) => StmtType::Block(vec![s, Stmt::new(body)]),
+	    Some(s) => Stmt::Block(stmt::Block::new(vec![s, body])),
 	    None => body,
 	};
 
 	Ok(body)
     }
 
-    fn expression(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn try_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let mut body = Vec::<Stmt>::new();
+	while !self.is_at_end() && self.peek().t_type != TokenType::Catch {
+	    body.push(self.declaration()?);
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::Catch)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect 'catch' after try block");
+		return Err(e)
+	    },
+	};
+	let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+	    Ok(t) => t.clone(),
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect error variable name after 'catch'");
+		return Err(e)
+	    },
+	};
+	let handler = self.block()?;
+	Ok(Stmt::Try(stmt::Try::new(body, name, handler)))
+    }
+
+    fn throw_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	let value = self.expression()?;
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ';' after thrown value");
+		return Err(e)
+	    },
+	};
+	Ok(Stmt::Throw(stmt::Throw::new(keyword, value)))
+    }
+
+    fn assert_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	let cond = self.expression()?;
+	let msg = match self.peek().t_type {
+	    TokenType::Comma => {
+		self.advance();
+		Some(self.expression()?)
+	    },
+	    _ => None,
+	};
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ';' after assertion");
+		return Err(e)
+	    },
+	};
+	Ok(Stmt::Assert(stmt::Assert::new(keyword, cond, msg)))
+    }
+
+    fn return_stmt(&mut self) -> Result<Stmt, Box<dyn Error>> {
+	let keyword = self.previous().clone();
+	if self.fn_depth == 0 {
+	    crate::report(keyword.line, &format!(" at '{}'", keyword.lexeme),
+			  "return outside of a function");
+	    return Err(Box::new(ParseError{}));
+	}
+	let in_init = self.init_depth == Some(self.fn_depth);
+	let value = match self.peek().t_type {
+	    TokenType::Semicolon => None,
+	    _ if in_init => {
+		crate::report(keyword.line, &format!(" at '{}'", keyword.lexeme),
+			      "can't return a value from init");
+		return Err(Box::new(ParseError{}));
+	    },
+	    _ => Some(self.expression()?),
+	};
+	match self.consume(|t_type| type_match!(t_type, TokenType::Semicolon)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ';' after return value");
+		return Err(e)
+	    },
+	};
+	Ok(Stmt::Return(stmt::Return::new(value)))
+    }
+
+    fn for_in_stmt(&mut self, label: Option<Token>) -> Result<Stmt, Box<dyn Error>> {
+	let name = self.advance().clone();
+	self.advance(); //in
+	let iterable = self.expression()?;
+	match self.consume(|t_type| type_match!(t_type, TokenType::Do)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect 'do' after for loop iterable");
+		return Err(e)
+	    },
+	};
+	let body = self.loop_body(&label)?;
+	Ok(Stmt::ForIn(stmt::ForIn::new(name, label, iterable, body)))
+    }
+
+    fn expression(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	self.assignment()
     }
 
-    fn assignment(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
-	let mut expr = self.logic_or()?;
+    fn assignment(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
+	let mut expr = self.conditional()?;
 
-	match self.peek().t_type {
-	    TokenType::Equal => {
+	if self.peek().t_type == TokenType::Equal {
 		self.advance();
 		let eq_line = self.previous().line;
 		let eq_lexeme = format!("{}", self.previous().lexeme);
 		let value = self.assignment()?;
 
-		match expr.kind() {
-		    ExprType::Variable => {
-			let name: String = format!("{}", expr.as_any().downcast_ref::<Variable>()
-						   .expect("downcast failed, fix parser::assignment")
-						   .name);
-			expr = Box::new(Assignment::new(name, value));
+		match *expr {
+		    Expr::Variable(v) => {
+			expr = Box::new(Expr::Assignment(Assignment::new(v.name, value)));
+		    },
+		    Expr::Get(get) => {
+			expr = Box::new(Expr::Set(Set::new(get.object, get.name, value)));
+		    },
+		    Expr::Tuple(_) => {
+			match to_pattern(expr.as_ref()) {
+			    Some(target) => expr = Box::new(Expr::Destructure(Destructure::new(expr.span(), target, value))),
+			    None => {
+				crate::report(eq_line, &format!(" at '{}'", eq_lexeme),
+					      "invalid l-value");
+				return Err(Box::new(ParseError{}));
+			    },
+			}
 		    },
 		    _ => {
 			crate::report(eq_line, &format!(" at '{}'", eq_lexeme),
@@ -359,13 +1294,56 @@ impl Parser {
 			return Err(Box::new(ParseError{}));
 		    },
 		}
+	    }
+	Ok(expr)
+    }
+
+    fn conditional(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
+	if self.peek().t_type == TokenType::If {
+	    let keyword = self.advance().clone();
+	    let cond = self.expression()?;
+	    match self.consume(|t_type| type_match!(t_type, TokenType::Then)) {
+		Ok(_) => {},
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "expect 'then' after if condition");
+		    return Err(e)
+		},
+	    };
+	    let then_expr = self.expression()?;
+	    match self.consume(|t_type| type_match!(t_type, TokenType::Else)) {
+		Ok(_) => {},
+		Err(e) => {
+		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				  "if expression needs an 'else'");
+		    return Err(e)
+		},
+	    };
+	    let else_expr = self.conditional()?;
+	    return Ok(Box::new(Expr::Conditional(Conditional::new(keyword, cond, then_expr, else_expr))));
+	}
+
+	let expr = self.logic_or()?;
+	match self.peek().t_type {
+	    TokenType::Question => {
+		let keyword = self.advance().clone();
+		let then_expr = self.expression()?;
+		match self.consume(|t_type| type_match!(t_type, TokenType::Colon)) {
+		    Ok(_) => {},
+		    Err(e) => {
+			crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+				      "expect ':' in conditional expression");
+			return Err(e)
+		    },
+		};
+		let else_expr = self.conditional()?;
+		Ok(Box::new(Expr::Conditional(Conditional::new(keyword, expr, then_expr, else_expr))))
 	    },
-	    _ => {},
+	    _ => Ok(expr),
 	}
-	Ok(expr)
     }
 
-    fn logic_or(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn logic_or(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	let mut expr = self.logic_and()?;
 	while !self.is_at_end() && match self.peek().t_type {
 	    TokenType::Or => {
@@ -378,12 +1356,12 @@ impl Parser {
 	} {
 	    let operator = self.previous().clone();
 	    let right = self.logic_and()?;
-	    expr = Box::new(Logical::new(expr, operator, right));
+	    expr = Box::new(Expr::Logical(Logical::new(expr, operator, right)));
 	}
 	Ok(expr)
     }
 
-    fn logic_and(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn logic_and(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	let mut expr = self.equality()?;
 	while !self.is_at_end() && match self.peek().t_type {
 	    TokenType::And => {
@@ -396,12 +1374,12 @@ impl Parser {
 	} {
 	    let operator = self.previous().clone();
 	    let right = self.equality()?;
-	    expr = Box::new(Logical::new(expr, operator, right));
+	    expr = Box::new(Expr::Logical(Logical::new(expr, operator, right)));
 	}
 	Ok(expr)
     }
 
-    fn equality(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn equality(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	let mut expr = self.comparison();
 
 	while !self.is_at_end() && match self.peek().t_type {
@@ -414,15 +1392,15 @@ impl Parser {
 	    let operator = self.previous().clone();
 	    let right = self.comparison();
 	    match right {
-		Ok(r) => expr = Ok(Box::new(Binary::new(expr?, operator, r))),
+		Ok(r) => expr = Ok(Box::new(Expr::Binary(Binary::new(expr?, operator, r)))),
 		Err(e) => expr = Err(e),
 	    }
 	}
 	expr
     }
 
-    fn comparison(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
-	let mut expr = self.term();
+    fn comparison(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
+	let mut expr = self.range();
 	while !self.is_at_end() && match self.peek().t_type {
 	    TokenType::Greater | TokenType::GreaterEqual |
 	    TokenType::Less | TokenType::LessEqual => {
@@ -432,16 +1410,35 @@ impl Parser {
 	    _ => false
 	} {
 	    let operator = self.previous().clone();
-	    let right = self.term();
+	    let right = self.range();
 	    match right {
-		Ok(r) => expr = Ok(Box::new(Binary::new(expr?, operator, r))),
+		Ok(r) => expr = Ok(Box::new(Expr::Binary(Binary::new(expr?, operator, r)))),
 		Err(e) => expr = Err(e),
 	    }
 	}
 	expr
     }
 
-    fn term(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn range(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
+	let start = self.term()?;
+	match self.peek().t_type {
+	    TokenType::DotDot | TokenType::DotDotEqual => {
+		let operator = self.advance().clone();
+		let end = self.term()?;
+		let step = match self.peek().t_type {
+		    TokenType::Step => {
+			self.advance();
+			Some(self.term()?)
+		    },
+		    _ => None,
+		};
+		Ok(Box::new(Expr::Range(Range::new(start, operator, end, step))))
+	    },
+	    _ => Ok(start),
+	}
+    }
+
+    fn term(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	let mut expr = self.factor();
 	while !self.is_at_end() && match self.peek().t_type {
 	    TokenType::Minus | TokenType::Plus => {
@@ -453,14 +1450,14 @@ impl Parser {
 	    let operator = self.previous().clone();
 	    let right = self.factor();
 	    match right {
-		Ok(r) => expr = Ok(Box::new(Binary::new(expr?, operator, r))),
+		Ok(r) => expr = Ok(Box::new(Expr::Binary(Binary::new(expr?, operator, r)))),
 		Err(e) => expr = Err(e),
 	    }
 	}
 	expr
     }
 
-    fn factor(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn factor(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	let mut expr = self.unary();
 	while !self.is_at_end() && match self.peek().t_type {
 	    TokenType::Slash | TokenType::Star => {
@@ -472,14 +1469,14 @@ impl Parser {
 	    let operator = self.previous().clone();
 	    let right = self.unary();
 	    match right {
-		Ok(r) => expr = Ok(Box::new(Binary::new(expr?, operator, r))),
+		Ok(r) => expr = Ok(Box::new(Expr::Binary(Binary::new(expr?, operator, r)))),
 		Err(e) => expr = Err(e),
 	    }
 	}
 	expr
     }
 
-    fn unary(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn unary(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	if !self.is_at_end() && match self.peek().t_type {
 	    TokenType::Bang | TokenType::Minus => {
 		self.advance();
@@ -490,52 +1487,172 @@ impl Parser {
 	    let operator = self.previous().clone();
 	    let right = self.unary();
 	    match right {
-		Ok(r) => return Ok(Box::new(Unary::new(operator, r))),
+		Ok(r) => return Ok(Box::new(Expr::Unary(Unary::new(operator, r)))),
 		Err(e) => return Err(e),
 	    }
 	}
-	let p = self.primary();
+	let p = self.call();
 	match p {
 	    Ok(exp) => Ok(exp),
 	    Err(e) => Err(e),
 	}
     }
 
-    fn primary(&mut self) -> Result<Box<dyn Expr>, Box<dyn Error>> {
+    fn call(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
+	let mut expr = self.primary()?;
+	loop {
+	    match self.peek().t_type {
+		TokenType::LParen => {
+		    let paren = self.advance().clone();
+		    let args = self.arguments()?;
+		    expr = Box::new(Expr::Call(Call::new(expr, paren, args)));
+		},
+		TokenType::LBracket => {
+		    let bracket = self.advance().clone();
+		    let index = self.expression()?;
+		    match self.consume(|t_type| type_match!(t_type, TokenType::RBracket)) {
+			Ok(_) => {},
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "expect ']' after index");
+			    return Err(e)
+			},
+		    };
+		    let span = expr.span().to(self.previous().span());
+		    expr = Box::new(Expr::Index(Index::new(span, expr, bracket, index)));
+		},
+		TokenType::Dot => {
+		    self.advance();
+		    let name = match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+			Ok(t) => t.clone(),
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "expect property name after '.'");
+			    return Err(e)
+			},
+		    };
+		    expr = Box::new(Expr::Get(Get::new(expr, name)));
+		},
+		_ => break,
+	    }
+	}
+	Ok(expr)
+    }
+
+    fn arguments(&mut self) -> Result<Vec<Arg>, Box<dyn Error>> {
+	let mut args = Vec::<Arg>::new();
+	if self.peek().t_type != TokenType::RParen {
+	    loop {
+		let start = self.peek().clone();
+		let name = match (&start.t_type, &self.peek_next().t_type) {
+		    (TokenType::Ident, TokenType::Colon) => {
+			self.advance();
+			self.advance();
+			Some(start.clone())
+		    },
+		    _ => None,
+		};
+		if name.is_none() && args.last().is_some_and(|a: &Arg| a.name.is_some()) {
+		    crate::report(start.line, &format!(" at '{}'", start.lexeme),
+				  "positional argument after a named one");
+		    return Err(Box::new(ParseError{}));
+		}
+		args.push(Arg {
+		    name,
+		    start,
+		    value: self.expression()?,
+		});
+		if self.peek().t_type != TokenType::Comma {
+		    break;
+		}
+		self.advance();
+	    }
+	}
+	match self.consume(|t_type| type_match!(t_type, TokenType::RParen)) {
+	    Ok(_) => {},
+	    Err(e) => {
+		crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+			      "expect ')' after arguments");
+		return Err(e)
+	    },
+	};
+	Ok(args)
+    }
+
+    fn primary(&mut self) -> Result<Box<Expr>, Box<dyn Error>> {
 	if !self.is_at_end() {
 	    //the clone is only needed for the string lit
 	    //which feels like something that could be improved
 	    match self.peek().t_type.clone() {
 		TokenType::False => {
 		    self.advance();
-		    Ok(Box::new(Literal::BoolLit(false)))
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::BoolLit(false)))))
 		},
 		TokenType::True => {
 		    self.advance();
-		    Ok(Box::new(Literal::BoolLit(true)))
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::BoolLit(true)))))
 		},
 		TokenType::Nil => {
 		    self.advance();
-		    Ok(Box::new(Literal::NilLit))
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::NilLit))))
 		},
 		TokenType::RealLit(r) => {
 		    self.advance();
-		    Ok(Box::new(Literal::RealLit(r)))
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::RealLit(r)))))
 		},
 		TokenType::IntLit(i) => {
 		    self.advance();
-		    Ok(Box::new(Literal::IntLit(i)))
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::IntLit(i)))))
 		},
 		TokenType::StrLit(s) => {
 		    self.advance();
-		    Ok(Box::new(Literal::StrLit(s)))
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::StrLit(s)))))
 		},
-		TokenType::LParen => {
+		TokenType::CharLit(c) => {
 		    self.advance();
+		    Ok(Box::new(Expr::Literal(Literal::new(self.previous().span(), Lit::CharLit(c)))))
+		},
+		TokenType::Int | TokenType::Char => {
+		    let target = self.advance().clone();
+		    match self.consume(|t_type| type_match!(t_type, TokenType::LParen)) {
+			Ok(_) => {},
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "expect '(' after conversion type");
+			    return Err(e)
+			},
+		    };
+		    let expr = self.expression()?;
+		    match self.consume(|t_type| type_match!(t_type, TokenType::RParen)) {
+			Ok(_) => Ok(Box::new(Expr::Cast(Cast::new(target.span().to(self.previous().span()), target, expr)))),
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "missing ')' after conversion");
+			    Err(e)
+			},
+		    }
+		},
+		TokenType::LParen => {
+		    let paren = self.advance().clone();
 		    let expr = self.expression();
+		    if expr.is_ok() && self.peek().t_type == TokenType::Comma {
+			let mut items = vec![*expr?];
+			while self.peek().t_type == TokenType::Comma {
+			    self.advance();
+			    items.push(*self.expression()?);
+			}
+			return match self.consume(|t_type| type_match!(t_type, TokenType::RParen)) {
+			    Ok(_) => Ok(Box::new(Expr::Tuple(Tuple::new(paren.span().to(self.previous().span()), paren, items)))),
+			    Err(e) => {
+				crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					      "missing ')' after tuple");
+				Err(e)
+			    },
+			};
+		    }
 		    //don't care about return here
 		    match self.consume(|t_type| type_match!(t_type, TokenType::RParen)) {
-			Ok(_) => Ok(Box::new(Grouping::new(expr?))),
+			Ok(_) => Ok(Box::new(Expr::Grouping(Grouping::new(paren.span().to(self.previous().span()), expr?)))),
 			Err(e) => {
 			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
 					  "missing ')' after expression");
@@ -543,9 +1660,70 @@ impl Parser {
 			},
 		    }
 		},
+		TokenType::LBracket => {
+		    let bracket = self.advance().span();
+		    let mut items = Vec::<Expr>::new();
+		    if self.peek().t_type != TokenType::RBracket {
+			items.push(*self.expression()?);
+			while self.peek().t_type == TokenType::Comma {
+			    self.advance();
+			    items.push(*self.expression()?);
+			}
+		    }
+		    match self.consume(|t_type| type_match!(t_type, TokenType::RBracket)) {
+			Ok(_) => Ok(Box::new(Expr::List(List::new(bracket.to(self.previous().span()), items)))),
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "missing ']' after list");
+			    Err(e)
+			},
+		    }
+		},
 		TokenType::Ident => {
 		    self.advance();
-		    Ok(Box::new(Variable::new(format!("{}", self.previous().lexeme))))
+		    Ok(Box::new(Expr::Variable(Variable::new(self.previous().clone()))))
+		},
+		TokenType::Fn | TokenType::Function => {
+		    let keyword = self.advance().clone();
+		    let def = self.function(keyword.clone(), None)?;
+		    Ok(Box::new(Expr::Lambda(Lambda::new(keyword.span().to(self.previous().span()), def))))
+		},
+		TokenType::SelfKw => {
+		    let keyword = self.advance().clone();
+		    if self.classes.is_empty() {
+			crate::report(keyword.line, &format!(" at '{}'", keyword.lexeme),
+				      "can't use 'self' outside of a class");
+			return Err(Box::new(ParseError{}));
+		    }
+		    Ok(Box::new(Expr::SelfExpr(SelfExpr::new(keyword))))
+		},
+		TokenType::Super => {
+		    let keyword = self.advance().clone();
+		    let msg = match self.classes.last() {
+			None => Some("can't use 'super' outside of a class"),
+			Some(false) => Some("can't use 'super' in a class with no superclass"),
+			Some(true) => None,
+		    };
+		    if let Some(msg) = msg {
+			crate::report(keyword.line, &format!(" at '{}'", keyword.lexeme), msg);
+			return Err(Box::new(ParseError{}));
+		    }
+		    match self.consume(|t_type| type_match!(t_type, TokenType::Dot)) {
+			Ok(_) => {},
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "expect '.' after 'super'");
+			    return Err(e)
+			},
+		    };
+		    match self.consume(|t_type| type_match!(t_type, TokenType::Ident)) {
+			Ok(t) => Ok(Box::new(Expr::Super(Super::new(keyword, t.clone())))),
+			Err(e) => {
+			    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
+					  "expect superclass method name");
+			    Err(e)
+			},
+		    }
 		},
 		_ => {
 		    crate::report(self.peek().line, &format!(" at '{}'", self.peek().lexeme),
@@ -571,23 +1749,39 @@ impl Parser {
 	    Err(Box::new(ParseError{}))
 	}
     }
+}
 
-    fn synchronize(&mut self) {
-	self.advance();
-	while !self.is_at_end() {
-	    if self.previous().t_type == TokenType::Semicolon {
-		return;
-	    }
-
-	    match self.peek().t_type {
-		TokenType::Function | TokenType::While |
-		TokenType::For | TokenType::If |
-		TokenType::Return | TokenType::Print => return,
-		_ => {},
-	    };
+//(a, b) = ... only makes sense when the tuple is all variables
+fn to_pattern(e: &Expr) -> Option<Pattern> {
+    match e {
+	Expr::Variable(v) => Some(Pattern::Name(None, v.name.clone(), v.node.id)),
+	Expr::Tuple(t) => {
+	    let items = t.items.iter().map(to_pattern).collect::<Option<Vec<Pattern>>>()?;
+	    Some(Pattern::Tuple(t.paren.clone(), items))
+	},
+	_ => None,
+    }
+}
 
-	    self.advance();
-	}
+//an operator method is called with the right operand, and
+//the comparisons have to give a bool
+fn check_operator(name: &Token, params: &[Param], ret: &Option<ValType>) -> Result<(), Box<dyn Error>> {
+    if name.t_type == TokenType::Ident {
+	return Ok(());
+    }
+    if params.len() != 1 || params[0].variadic {
+	crate::report(name.line, &format!(" at '{}'", name.lexeme),
+		      "an operator method takes exactly one parameter");
+	return Err(Box::new(ParseError{}));
+    }
+    let comparison = !matches!(name.t_type, TokenType::Plus | TokenType::Minus | TokenType::Star | TokenType::Slash);
+    match ret {
+	Some(t) if comparison && *t != ValType::Bool => {
+	    crate::report(name.line, &format!(" at '{}'", name.lexeme),
+			  &format!("operator '{}' has to return bool", name.lexeme));
+	    Err(Box::new(ParseError{}))
+	},
+	_ => Ok(()),
     }
 }
 
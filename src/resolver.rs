@@ -0,0 +1,530 @@
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::rc::Rc;
+use crate::expr;
+use crate::expr::{Expr, ExprVisitor, FnDef, FnBody, NodeId, Pattern};
+use crate::stmt;
+use crate::stmt::{Stmt, StmtVisitor};
+use crate::token::Token;
+
+//binds every variable to the scope it was declared in, counted outwards
+//from where it's used, so the interpreter goes straight to the right
+//environment. the scopes here have to match the environments the
+//interpreter makes exactly (see "Scopes" in spec.md)
+
+//where a local variable is declared, from one of its uses
+#[derive (Debug, Clone, Copy, PartialEq)]
+pub struct Depth {
+    //how many scopes out, counting every scope in between
+    pub scopes: usize,
+    //how many functions out, 0 for the function using it, which is
+    //the static link the interpreter follows to get to it
+    pub fns: usize,
+    //how many scopes out from where it starts: the use itself when fns
+    //is 0, otherwise the scope the link points to
+    pub from_link: usize,
+}
+
+//where each local variable's uses and assignments are declared, by
+//node. globals aren't in it
+pub type Depths = HashMap<NodeId, Depth>;
+
+#[derive (Clone, Copy, PartialEq)]
+enum State {
+    //declared further down, so only a function can see it from here
+    Later,
+    //its initializer is being resolved
+    Declared,
+    Defined,
+}
+
+struct Scope {
+    names: HashMap<Rc<String>, State>,
+    //how many functions in the scope is, a name declared later is
+    //fine to use from a function since it won't run until it's called
+    fn_depth: usize,
+    //the names declared with function, for checking the arguments of calls to them
+    fns: HashMap<Rc<String>, Rc<FnDef>>,
+}
+
+impl Scope {
+    fn new(fn_depth: usize) -> Self {
+	Scope {
+	    names: HashMap::new(),
+	    fn_depth,
+	    fns: HashMap::new(),
+	}
+    }
+}
+
+pub struct Resolver {
+    //the first scope is the globals
+    scopes: Vec<Scope>,
+    fn_depth: usize,
+    //globals declared by earlier lines at the prompt
+    known: HashSet<Rc<String>>,
+    known_fns: HashMap<Rc<String>, Rc<FnDef>>,
+    depths: Depths,
+    had_error: bool,
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+	Resolver {
+	    scopes: Vec::new(),
+	    fn_depth: 0,
+	    known: HashSet::new(),
+	    known_fns: HashMap::new(),
+	    depths: Depths::new(),
+	    had_error: false,
+	}
+    }
+
+    //errors are all reported as they're found, the script doesn't run if there were any
+    pub fn resolve(&mut self, ast: &[Stmt]) -> Result<Depths, Box<dyn Error>> {
+	self.had_error = false;
+	self.depths.clear();
+	self.fn_depth = 0;
+	self.scopes = vec![Scope::new(0)];
+	self.hoist(ast);
+	for stmt in ast.iter() {
+	    stmt.accept(self);
+	}
+	let globals = self.scopes.pop().expect("no globals, fix resolver::resolve");
+	if self.had_error {
+	    return Err(Box::new(ResolveError {}));
+	}
+	for name in globals.names.into_keys() {
+	    match globals.fns.get(&name) {
+		Some(def) => self.known_fns.insert(name.clone(), def.clone()),
+		None => self.known_fns.remove(&name),
+	    };
+	    self.known.insert(name);
+	}
+	Ok(std::mem::take(&mut self.depths))
+    }
+
+    fn error(&mut self, name: &Token, msg: &str) {
+	crate::report(name.line, &format!(" at '{}'", name.lexeme), msg);
+	self.had_error = true;
+    }
+
+    //everything declared directly in stmts is in scope from the start,
+    //which is what lets functions call each other whichever comes first
+    fn hoist(&mut self, stmts: &[Stmt]) {
+	let scope = self.scopes.last_mut().expect("no scope, fix resolver::hoist");
+	for stmt in stmts.iter() {
+	    for name in stmt.declares() {
+		scope.names.entry(name.lexeme.clone()).or_insert(State::Later);
+	    }
+	    if let Stmt::FnDecl(f) = stmt
+		&& let Expr::Lambda(l) = f.init.as_ref() {
+		scope.fns.insert(f.name.lexeme.clone(), l.def.clone());
+	    }
+	}
+    }
+
+    fn declare(&mut self, name: &Token) {
+	let scope = self.scopes.last_mut().expect("no scope, fix resolver::declare");
+	match scope.names.insert(name.lexeme.clone(), State::Declared) {
+	    Some(State::Declared) | Some(State::Defined) => {
+		self.error(name, "duplicate declaration in same scope");
+	    },
+	    _ => {},
+	}
+    }
+
+    fn define(&mut self, name: &Token) {
+	self.scopes.last_mut().expect("no scope, fix resolver::define")
+	    .names.insert(name.lexeme.clone(), State::Defined);
+    }
+
+    //name used by the node id, where the passes after this one look it up
+    fn resolve_at(&mut self, id: NodeId, name: &Token) {
+	if let Some(d) = self.resolve_name(name) {
+	    self.depths.insert(id, d);
+	}
+    }
+
+    fn resolve_pattern(&mut self, p: &Pattern) {
+	match p {
+	    Pattern::Name(_, n, id) => self.resolve_at(*id, n),
+	    Pattern::Tuple(_, items) => {
+		for i in items.iter() {
+		    self.resolve_pattern(i);
+		}
+	    },
+	}
+    }
+
+    //where name is declared, None for a global
+    fn resolve_name(&mut self, name: &Token) -> Option<Depth> {
+	let mut found = None;
+	for (i, scope) in self.scopes.iter().enumerate().rev() {
+	    match scope.names.get(&name.lexeme) {
+		Some(State::Later) if scope.fn_depth == self.fn_depth => {},
+		Some(s) => {
+		    found = Some((i, *s, scope.fn_depth));
+		    break;
+		},
+		None => {},
+	    }
+	}
+	match found {
+	    //from a function in the initializer it's fine, the function can't
+	    //be called before the variable is there
+	    Some((_, State::Declared, d)) if d == self.fn_depth => {
+		self.error(name, "use of variable in its own initializer");
+		None
+	    },
+	    Some((0, ..)) => None,
+	    Some((i, _, d)) => {
+		//a link points at the innermost scope of the function it goes out to
+		let from = self.scopes.iter().rposition(|s| s.fn_depth == d)
+		    .expect("no scope for the link, fix resolver::resolve_name");
+		Some(Depth {
+		    scopes: self.scopes.len() - 1 - i,
+		    fns: self.fn_depth - d,
+		    from_link: from - i,
+		})
+	    },
+	    None if self.known.contains(&name.lexeme) => None,
+	    None => {
+		self.error(name, "undefined variable");
+		None
+	    },
+	}
+    }
+
+    //the function a call by name goes to, if the name was declared with
+    //function (or fn f = fn ...) and not a parameter or variable shadowing it
+    fn declared_fn(&self, name: &Token) -> Option<Rc<FnDef>> {
+	for scope in self.scopes.iter().rev() {
+	    match scope.names.get(&name.lexeme) {
+		Some(State::Later) if scope.fn_depth == self.fn_depth => {},
+		Some(_) => return scope.fns.get(&name.lexeme).cloned(),
+		None => {},
+	    }
+	}
+	self.known_fns.get(&name.lexeme).cloned()
+    }
+
+    //an argument for a var parameter has to be something that can be assigned to
+    fn check_var_args(&mut self, callee: &Token, def: &FnDef, args: &[expr::Arg]) {
+	let variadic = def.params.last().is_some_and(|p| p.variadic);
+	for (i, arg) in args.iter().enumerate() {
+	    let param = match &arg.name {
+		Some(n) => def.params.iter().find(|p| p.name.lexeme == n.lexeme),
+		None if i + 1 >= def.params.len() && variadic => def.params.last(),
+		None => def.params.get(i),
+	    };
+	    match param {
+		Some(p) if p.by_ref && !matches!(arg.value.as_ref(), Expr::Variable(_)) => {
+		    self.error(&arg.start, &format!("argument for var parameter '{}' of '{}' must be a variable",
+						    p.name.lexeme, callee.lexeme));
+		},
+		_ => {},
+	    }
+	}
+    }
+
+    fn scoped(&mut self, stmts: &[Stmt], name: Option<&Token>) {
+	self.scopes.push(Scope::new(self.fn_depth));
+	if let Some(n) = name {
+	    self.declare(n);
+	    self.define(n);
+	}
+	self.hoist(stmts);
+	for stmt in stmts.iter() {
+	    stmt.accept(self);
+	}
+	self.scopes.pop();
+    }
+
+    //a scope holding just one name the interpreter puts there, self or super
+    fn implicit(&mut self, name: &str) {
+	let mut scope = Scope::new(self.fn_depth);
+	scope.names.insert(Rc::new(name.to_string()), State::Defined);
+	self.scopes.push(scope);
+    }
+
+    //the parameters and the body share a scope, same as at runtime
+    fn function(&mut self, def: &FnDef) {
+	self.fn_depth += 1;
+	self.scopes.push(Scope::new(self.fn_depth));
+	//defaults are evaluated after the parameters before them are bound
+	for p in def.params.iter() {
+	    if let Some(d) = &p.default {
+		d.accept(self);
+	    }
+	    self.declare(&p.name);
+	    self.define(&p.name);
+	}
+	match &def.body {
+	    FnBody::Expr(b) => {
+		b.accept(self);
+	    },
+	    FnBody::Block(stmts) => {
+		self.hoist(stmts);
+		for stmt in stmts.iter() {
+		    stmt.accept(self);
+		}
+	    },
+	}
+	self.scopes.pop();
+	self.fn_depth -= 1;
+    }
+}
+
+impl ExprVisitor<()> for Resolver {
+    fn visit_binary(&mut self, e: &expr::Binary) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_unary(&mut self, e: &expr::Unary) {
+	e.right.accept(self)
+    }
+
+    fn visit_grouping(&mut self, e: &expr::Grouping) {
+	e.expression.accept(self)
+    }
+
+    fn visit_literal(&mut self, _e: &expr::Literal) {}
+
+    fn visit_assignment(&mut self, e: &expr::Assignment) {
+	e.val.accept(self);
+	self.resolve_at(e.node.id, &e.name);
+    }
+
+    fn visit_variable(&mut self, e: &expr::Variable) {
+	self.resolve_at(e.node.id, &e.name);
+    }
+
+    fn visit_logical(&mut self, e: &expr::Logical) {
+	e.left.accept(self);
+	e.right.accept(self)
+    }
+
+    fn visit_cast(&mut self, e: &expr::Cast) {
+	e.expr.accept(self)
+    }
+
+    fn visit_conditional(&mut self, e: &expr::Conditional) {
+	e.cond.accept(self);
+	e.then_expr.accept(self);
+	e.else_expr.accept(self)
+    }
+
+    fn visit_lambda(&mut self, e: &expr::Lambda) {
+	self.function(&e.def);
+    }
+
+    fn visit_call(&mut self, e: &expr::Call) {
+	e.callee.accept(self);
+	if let Expr::Variable(v) = e.callee.as_ref()
+	    && let Some(def) = self.declared_fn(&v.name) {
+	    self.check_var_args(&v.name, &def, &e.args);
+	}
+	for arg in e.args.iter() {
+	    arg.value.accept(self);
+	}
+    }
+
+    fn visit_index(&mut self, e: &expr::Index) {
+	e.object.accept(self);
+	e.index.accept(self)
+    }
+
+    fn visit_range(&mut self, e: &expr::Range) {
+	e.start.accept(self);
+	e.end.accept(self);
+	if let Some(s) = &e.step {
+	    s.accept(self);
+	}
+    }
+
+    fn visit_tuple(&mut self, e: &expr::Tuple) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_list(&mut self, e: &expr::List) {
+	for i in e.items.iter() {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_destructure(&mut self, e: &expr::Destructure) {
+	e.val.accept(self);
+	self.resolve_pattern(&e.target);
+    }
+
+    fn visit_get(&mut self, e: &expr::Get) {
+	e.object.accept(self)
+    }
+
+    fn visit_set(&mut self, e: &expr::Set) {
+	e.object.accept(self);
+	e.val.accept(self)
+    }
+
+    //the parser has already made sure these are inside a class
+    fn visit_self(&mut self, e: &expr::SelfExpr) {
+	self.resolve_at(e.node.id, &e.keyword);
+    }
+
+    //self is always the scope just inside super, so the interpreter
+    //finds it from here too
+    fn visit_super(&mut self, e: &expr::Super) {
+	self.resolve_at(e.node.id, &e.keyword);
+    }
+}
+
+impl StmtVisitor<()> for Resolver {
+    fn visit_print(&mut self, s: &stmt::Print) {
+	let stmt::Print{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_expression(&mut self, s: &stmt::Expression) {
+	let stmt::Expression{expr: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_typed_decl(&mut self, s: &stmt::TypedDecl) {
+	let stmt::TypedDecl{name: n, init: e, ..} = s;
+	self.declare(n);
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+	self.define(n);
+    }
+
+    fn visit_vardecl(&mut self, s: &stmt::VarDecl) {
+	let stmt::VarDecl{pattern: p, init: e, ..} = s;
+	for n in p.names() {
+	    self.declare(n);
+	}
+	e.accept(self);
+	for n in p.names() {
+	    self.define(n);
+	}
+    }
+
+    fn visit_decls(&mut self, s: &stmt::Decls) {
+	let stmt::Decls{decls: s, ..} = s;
+	for stmt in s.iter() {
+	    stmt.accept(self);
+	}
+    }
+
+    fn visit_fndecl(&mut self, s: &stmt::FnDecl) {
+	let stmt::FnDecl{name: n, init: e, ..} = s;
+	//defined first so a function can call itself
+	self.declare(n);
+	self.define(n);
+	e.accept(self)
+    }
+
+    fn visit_class(&mut self, s: &stmt::Class) {
+	let stmt::Class{name: n, superclass: sup, methods, ..} = s;
+	self.declare(n);
+	self.define(n);
+	if let Some(sup) = sup {
+	    sup.accept(self);
+	    self.implicit("super");
+	}
+	for m in methods.iter() {
+	    self.implicit("self");
+	    self.function(m);
+	    self.scopes.pop();
+	}
+	if sup.is_some() {
+	    self.scopes.pop();
+	}
+    }
+
+    fn visit_interface(&mut self, _s: &stmt::Interface) {}
+
+    fn visit_block(&mut self, s: &stmt::Block) {
+	let stmt::Block{stmts: s, ..} = s;
+	self.scoped(s, None);
+    }
+
+    fn visit_if(&mut self, s: &stmt::If) {
+	let stmt::If{cond: c, then_branch: t, else_branch: e, ..} = s;
+	c.accept(self);
+	t.accept(self);
+	if let Some(e) = e {
+	    e.accept(self);
+	}
+    }
+
+    fn visit_while(&mut self, s: &stmt::While) {
+	let stmt::While{cond: c, body: s, increment: i, ..} = s;
+	c.accept(self);
+	s.accept(self);
+	if let Some(i) = i {
+	    i.accept(self);
+	}
+    }
+
+    fn visit_forin(&mut self, s: &stmt::ForIn) {
+	let stmt::ForIn{name: n, iterable: e, body, ..} = s;
+	e.accept(self);
+	self.scoped(body, Some(n));
+    }
+
+    fn visit_repeat(&mut self, s: &stmt::Repeat) {
+	let stmt::Repeat{body: s, cond: c, ..} = s;
+	s.accept(self);
+	c.accept(self)
+    }
+
+    fn visit_loop(&mut self, s: &stmt::Loop) {
+	let stmt::Loop{body: s, ..} = s;
+	s.accept(self)
+    }
+
+    fn visit_break(&mut self, _s: &stmt::Break) {}
+
+    fn visit_continue(&mut self, _s: &stmt::Continue) {}
+
+    fn visit_throw(&mut self, s: &stmt::Throw) {
+	let stmt::Throw{value: e, ..} = s;
+	e.accept(self)
+    }
+
+    fn visit_try(&mut self, s: &stmt::Try) {
+	let stmt::Try{body, name, handler, ..} = s;
+	self.scoped(body, None);
+	self.scoped(handler, Some(name));
+    }
+
+    fn visit_assert(&mut self, s: &stmt::Assert) {
+	let stmt::Assert{cond: c, message: m, ..} = s;
+	c.accept(self);
+	if let Some(m) = m {
+	    m.accept(self);
+	}
+    }
+
+    fn visit_return(&mut self, s: &stmt::Return) {
+	if let Some(e) = &s.value {
+	    e.accept(self);
+	}
+    }
+}
+
+#[derive (Debug)]
+struct ResolveError {}
+
+impl std::fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+	write!(f, "resolve error")
+    }
+}
+
+impl Error for ResolveError {}
@@ -6,22 +6,31 @@ use crate::token::TokenType;
 use crate::token::Token;
 
 pub struct Scanner {
-    source: String,
+    //chars rather than the string, so start and current can index it
+    source: Vec<char>,
     pub tokens: Vec<Token>,
     start: usize,
     current: usize,
     line: u32,
+    pub had_error: bool,
+    //the text of each //comment and its line, for lint pragmas
+    pub comments: Vec<(u32, String)>,
+    //restrict char literals to a single byte (PETSCII/ASCII)
+    pub byte_chars: bool,
     keywords: HashMap<&'static str, TokenType>,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Self {
 	Scanner {
-	    source: source,
+	    source: source.chars().collect(),
 	    tokens: Vec::<Token>::new(),
 	    start: 0,
 	    current: 0,
 	    line: 1,
+	    had_error: false,
+	    comments: Vec::new(),
+	    byte_chars: false,
 	    keywords: HashMap::from([
 		("begin", TokenType::Begin),
 		("end", TokenType::End),
@@ -43,6 +52,28 @@ impl Scanner {
 		("int", TokenType::Int),
 		("str", TokenType::Str),
 		("bool", TokenType::Bool),
+		("char", TokenType::Char),
+		("try", TokenType::Try),
+		("catch", TokenType::Catch),
+		("throw", TokenType::Throw),
+		("assert", TokenType::Assert),
+		("fn", TokenType::Fn),
+		("in", TokenType::In),
+		("step", TokenType::Step),
+		("repeat", TokenType::Repeat),
+		("until", TokenType::Until),
+		("loop", TokenType::Loop),
+		("break", TokenType::Break),
+		("continue", TokenType::Continue),
+		("var", TokenType::Var),
+		("class", TokenType::Class),
+		("self", TokenType::SelfKw),
+		("super", TokenType::Super),
+		("interface", TokenType::Interface),
+		("implements", TokenType::Implements),
+		("list", TokenType::List),
+		("tuple", TokenType::Tuple),
+		("operator", TokenType::Operator),
 		]),
 	}
     }
@@ -51,14 +82,24 @@ impl Scanner {
 	//let mut tokens = Vec::<Token>::new();
 	while !self.is_at_end() {
 	    self.start = self.current;
+	    let count = self.tokens.len();
 	    self.scan_token();
+	    //the token, if there is one, is everything scan_token just went past
+	    if self.tokens.len() > count {
+		let token = self.tokens.last_mut().expect("no token, fix scanner::scan_tokens");
+		token.start = self.start;
+		token.end = self.current;
+	    }
 	}
 
-	self.tokens.push(Token::new(TokenType::EOF, "\0".to_string(), self.line));
+	let mut eof = Token::new(TokenType::EOF, "\0".to_string(), self.line);
+	eof.start = self.current;
+	eof.end = self.current;
+	self.tokens.push(eof);
     }
 
     fn is_at_end(&self) -> bool {
-	self.current >= self.source.chars().count()
+	self.current >= self.source.len()
     }
 
     fn scan_token(&mut self) {
@@ -68,11 +109,37 @@ impl Scanner {
 	    '(' => self.tokens.push(Token::new(TokenType::LParen, c.to_string(), self.line)),
 	    ')' => self.tokens.push(Token::new(TokenType::RParen, c.to_string(), self.line)),
 	    ',' => self.tokens.push(Token::new(TokenType::Comma, c.to_string(), self.line)),
-	    '.' => self.tokens.push(Token::new(TokenType::Dot, c.to_string(), self.line)),
-	    '-' => self.tokens.push(Token::new(TokenType::Minus, c.to_string(), self.line)),
+	    '.' => match (self.peek(), self.peek_next()) {
+		('.', '.') => {
+		    self.advance();
+		    self.advance();
+		    self.tokens.push(Token::new(TokenType::Ellipsis, "...".to_string(), self.line))
+		},
+		('.', '=') => {
+		    self.advance();
+		    self.advance();
+		    self.tokens.push(Token::new(TokenType::DotDotEqual, "..=".to_string(), self.line))
+		},
+		('.', _) => {
+		    self.advance();
+		    self.tokens.push(Token::new(TokenType::DotDot, "..".to_string(), self.line))
+		},
+		_ => self.tokens.push(Token::new(TokenType::Dot, c.to_string(), self.line)),
+	    },
+	    '[' => self.tokens.push(Token::new(TokenType::LBracket, c.to_string(), self.line)),
+	    ']' => self.tokens.push(Token::new(TokenType::RBracket, c.to_string(), self.line)),
+	    '-' => match self.peek() == '>' {
+		true => {
+		    self.advance();
+		    self.tokens.push(Token::new(TokenType::Arrow, "->".to_string(), self.line))
+		},
+		false => self.tokens.push(Token::new(TokenType::Minus, c.to_string(), self.line)),
+	    },
 	    '+' => self.tokens.push(Token::new(TokenType::Plus, c.to_string(), self.line)),
 	    ';' => self.tokens.push(Token::new(TokenType::Semicolon, c.to_string(), self.line)),
 	    '*' => self.tokens.push(Token::new(TokenType::Star, c.to_string(), self.line)),
+	    '?' => self.tokens.push(Token::new(TokenType::Question, c.to_string(), self.line)),
+	    ':' => self.tokens.push(Token::new(TokenType::Colon, c.to_string(), self.line)),
 	    '=' => match eq_next {
 		true => {
 		    self.advance();
@@ -103,9 +170,11 @@ impl Scanner {
 	    },
 	    '/' => {
 		if self.check('/') {
+		    let mut text = String::new();
 		    while self.peek() != '\n' && !self.is_at_end() {
-			self.advance();
+			text.push(self.advance());
 		    }
+		    self.comments.push((self.line, text));
 		} else {
 		    self.tokens.push(Token::new(TokenType::Slash, c.to_string(), self.line));
 		}
@@ -113,20 +182,23 @@ impl Scanner {
 	    ' ' | '\r' | '\t' => {},
 	    '\n' => self.line += 1,
 	    '"' => self.string(),
+	    '\'' => self.character(),
+	    '$' if self.peek().is_ascii_hexdigit() => self.radix_number(16),
+	    '%' if self.peek().is_digit(2) => self.radix_number(2),
 	    _ => {
 		if self.is_digit(c) {
-		    self.number();
+		    self.number(c);
 		} else if self.is_alpha(c) {
 		    self.identifier();
 		} else {
-		    prerror(self.line, "Unexpected character");
+		    self.error("Unexpected character");
 		}
 	    },
 	};
     }
 
     fn advance(&mut self) -> char {
-	let c = self.source.chars().nth(self.current).unwrap();
+	let c = self.source[self.current];
 	self.current += 1;
 	c
     }
@@ -135,7 +207,7 @@ impl Scanner {
 	if self.is_at_end() {
 	    return false;
 	}
-	if self.source.chars().nth(self.current).unwrap() != c {
+	if self.source[self.current] != c {
 	    return false;
 	}
 	self.current += 1;
@@ -146,7 +218,7 @@ impl Scanner {
 	if self.is_at_end() {
 	    return '\0';
 	}
-	return self.source.chars().nth(self.current).unwrap();
+	self.source[self.current]
     }
 
     fn string(&mut self) {
@@ -158,23 +230,63 @@ impl Scanner {
 	}
 
 	if self.is_at_end() {
-	    prerror(self.line, "Unterminated string");
+	    self.error("Unterminated string");
+	    return;
 	}
 
 	self.advance(); //closing quote
 
-	let string: String = self.source[self.start + 1..self.current - 1].to_string();
-	let lexeme: String = self.source[self.start..self.current].to_string();
+	let string = self.text(self.start + 1, self.current - 1);
+	let lexeme = self.text(self.start, self.current);
 	self.tokens.push(Token::new(TokenType::StrLit(Rc::new(string)), lexeme, self.line));
     }
 
+    fn character(&mut self) {
+	let c = match self.peek() {
+	    '\'' | '\n' | '\0' => {
+		self.error("Empty character literal");
+		return;
+	    },
+	    '\\' => {
+		self.advance();
+		match self.advance() {
+		    'n' => '\n',
+		    'r' => '\r',
+		    't' => '\t',
+		    '0' => '\0',
+		    '\\' => '\\',
+		    '\'' => '\'',
+		    '"' => '"',
+		    _ => {
+			self.error("Unknown escape in character literal");
+			return;
+		    },
+		}
+	    },
+	    _ => self.advance(),
+	};
+
+	if !self.check('\'') {
+	    self.error("Unterminated character literal");
+	    return;
+	}
+
+	if self.byte_chars && c as u32 > 0xff {
+	    self.error("Character literal does not fit in a byte");
+	    return;
+	}
+
+	let lexeme = self.text(self.start, self.current);
+	self.tokens.push(Token::new(TokenType::CharLit(c), lexeme, self.line));
+    }
+
     fn is_digit(&mut self, c: char) -> bool {
-	c >= '0' && c <= '9'
+	c.is_ascii_digit()
     }
 
     fn is_alpha(&mut self, c: char) -> bool {
-	(c >= 'a' && c <= 'z') ||
-	    (c >= 'A' && c <= 'Z') ||
+	c.is_ascii_lowercase() ||
+	    c.is_ascii_uppercase() ||
 	    c == '_'
     }
 
@@ -182,50 +294,146 @@ impl Scanner {
 	self.is_digit(c) || self.is_alpha(c)
     }
 
-    fn number(&mut self) {
-	let mut was_float = false;
-	while let c = self.peek() && self.is_digit(c) {
-	    self.advance();
+    fn number(&mut self, first: char) {
+	if first == '0' {
+	    let c = self.peek_next();
+	    match self.peek() {
+		'x' | 'X' if c.is_ascii_hexdigit() => {
+		    self.advance();
+		    return self.radix_number(16);
+		},
+		'b' | 'B' if c.is_digit(2) => {
+		    self.advance();
+		    return self.radix_number(2);
+		},
+		_ => {},
+	    }
 	}
 
+	let mut was_float = false;
+	self.digits(10);
+
 	let c = self.peek_next();
 	if self.peek() == '.' && self.is_digit(c) {
 	    was_float = true;
 	    self.advance();
-	    while let c = self.peek() && self.is_digit(c) {
+	    self.digits(10);
+	}
+
+	//exponent, only taken if there's actually a digit after the 'e'
+	//so that something like 2else doesn't get eaten
+	if self.peek() == 'e' || self.peek() == 'E' {
+	    let c = self.peek_next();
+	    let sign = c == '+' || c == '-';
+	    let c = if sign { self.peek_nth(2) } else { c };
+	    if self.is_digit(c) {
+		was_float = true;
 		self.advance();
+		if sign {
+		    self.advance();
+		}
+		self.digits(10);
 	    }
 	}
 
-	let numstr = &self.source[self.start..self.current];
-	match was_float {
-	    true => {
-		let number = numstr.parse::<f32>().unwrap();
-		self.tokens.push(Token::new(TokenType::RealLit(number), numstr.to_string(), self.line));
+	let digits_end = self.current;
+	let suffix = self.suffix();
+	let numstr: String = self.source[self.start..digits_end].iter()
+	    .filter(|c| **c != '_')
+	    .collect();
+
+	match (was_float, suffix) {
+	    (true, Some('i')) => {
+		self.error("int suffix on real literal");
+	    },
+	    (true, _) | (false, Some('r')) => {
+		match numstr.parse::<f32>() {
+		    Ok(r) if r.is_finite() => self.add_number(TokenType::RealLit(r)),
+		    _ => self.error("real literal out of range"),
+		}
+	    },
+	    (false, _) => {
+		match numstr.parse::<u32>() {
+		    Ok(i) => self.add_number(TokenType::IntLit(i)),
+		    Err(_) => self.error("integer literal out of range"),
+		}
 	    },
-	    false => {
-		let number = numstr.parse::<u32>().unwrap();
-		self.tokens.push(Token::new(TokenType::IntLit(number), numstr.to_string(), self.line));
+	};
+    }
+
+    //$FF and %1010 style literals, as well as 0xFF and 0b1010
+    //the prefix has already been consumed
+    fn radix_number(&mut self, radix: u32) {
+	let digits_start = self.current;
+	self.digits(radix);
+	let digits_end = self.current;
+	let suffix = self.suffix();
+	let numstr: String = self.source[digits_start..digits_end].iter()
+	    .filter(|c| **c != '_')
+	    .collect();
+
+	match u32::from_str_radix(&numstr, radix) {
+	    Ok(i) => match suffix {
+		Some('r') => self.add_number(TokenType::RealLit(i as f32)),
+		_ => self.add_number(TokenType::IntLit(i)),
 	    },
+	    Err(_) => self.error("integer literal out of range"),
 	};
     }
 
+    //digits in the given radix, with _ allowed as a separator
+    fn digits(&mut self, radix: u32) {
+	while let c = self.peek() && (c.is_digit(radix) || c == '_') {
+	    self.advance();
+	}
+    }
+
+    //optional type suffix, 'i' for int or 'r' for real. anything longer
+    //is left for the next token, so 0..2do is still a range and a do
+    fn suffix(&mut self) -> Option<char> {
+	let c = self.peek();
+	let next = self.peek_next();
+	if (c == 'i' || c == 'r') && !self.is_alphanumeric(next) {
+	    self.advance();
+	    return Some(c);
+	}
+	None
+    }
+
+    fn add_number(&mut self, t_type: TokenType) {
+	let lexeme = self.text(self.start, self.current);
+	self.tokens.push(Token::new(t_type, lexeme, self.line));
+    }
+
     fn identifier(&mut self) {
 	while let c = self.peek() && self.is_alphanumeric(c) {
 	    self.advance();
 	}
 
-	let text = &self.source[self.start..self.current];
-	match self.keywords.get(text) {
-	    Some(t_type) => self.tokens.push(Token::new(t_type.clone(), text.to_string(), self.line)),
-	    None => self.tokens.push(Token::new(TokenType::Ident, text.to_string(), self.line)),
+	let text = self.text(self.start, self.current);
+	match self.keywords.get(text.as_str()) {
+	    Some(t_type) => self.tokens.push(Token::new(t_type.clone(), text, self.line)),
+	    None => self.tokens.push(Token::new(TokenType::Ident, text, self.line)),
 	};
     }
 
     fn peek_next(&mut self) -> char {
-	if self.current + 1 >= self.source.chars().count() {
-	    return '\0';
+	self.peek_nth(1)
+    }
+
+    fn peek_nth(&mut self, n: usize) -> char {
+	match self.source.get(self.current + n) {
+	    Some(c) => *c,
+	    None => '\0',
 	}
-	return self.source.chars().nth(self.current + 1).unwrap();
+    }
+
+    fn text(&self, start: usize, end: usize) -> String {
+	self.source[start..end].iter().collect()
+    }
+
+    fn error(&mut self, msg: &str) {
+	self.had_error = true;
+	prerror(self.line, msg);
     }
 }
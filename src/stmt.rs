@@ -1,120 +1,733 @@
-use std::error::Error;
 use std::rc::Rc;
-use crate::expr::Expr;
+use crate::expr::{Expr, Pattern, FnDef, MethodSig, Node, ValType};
+use crate::token::{Token, Span};
 
-pub enum StmtType {
-    Print(Box<dyn Expr>),
-    Expression(Box<dyn Expr>),
-    IntDecl(Rc<String>, Option<Box<dyn Expr>>),
-    RealDecl(Rc<String>, Option<Box<dyn Expr>>),
-    StrDecl(Rc<String>, Option<Box<dyn Expr>>),
-    Block(Vec<Stmt>),
-    If(Box<dyn Expr>, Box<Stmt>, Option<Box<Stmt>>),
-    While(Box<dyn Expr>, Box<Stmt>),
+//each kind of statement is its own struct, so a visitor gets the exact node
+#[derive (Clone)]
+pub enum Stmt {
+    Print(Print),
+    Expression(Expression),
+    TypedDecl(TypedDecl),
+    VarDecl(VarDecl),
+    Decls(Decls),
+    FnDecl(FnDecl),
+    Block(Block),
+    Class(Class),
+    Interface(Interface),
+    If(If),
+    While(While),
+    Repeat(Repeat),
+    Loop(Loop),
+    ForIn(ForIn),
+    Break(Break),
+    Continue(Continue),
+    Throw(Throw),
+    Try(Try),
+    Assert(Assert),
+    Return(Return),
 }
 
-pub trait StmtVisitor {
-    fn visit_print(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_expression(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_intdecl(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_realdecl(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_strdecl(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_block(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_if(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
-    fn visit_while(&mut self, s: &StmtType) -> Result<(), Box<dyn Error>>;
+pub trait StmtVisitor<T> {
+    fn visit_print(&mut self, s: &Print) -> T;
+    fn visit_expression(&mut self, s: &Expression) -> T;
+    fn visit_typed_decl(&mut self, s: &TypedDecl) -> T;
+    fn visit_vardecl(&mut self, s: &VarDecl) -> T;
+    fn visit_decls(&mut self, s: &Decls) -> T;
+    fn visit_fndecl(&mut self, s: &FnDecl) -> T;
+    fn visit_block(&mut self, s: &Block) -> T;
+    fn visit_class(&mut self, s: &Class) -> T;
+    fn visit_interface(&mut self, s: &Interface) -> T;
+    fn visit_if(&mut self, s: &If) -> T;
+    fn visit_while(&mut self, s: &While) -> T;
+    fn visit_forin(&mut self, s: &ForIn) -> T;
+    fn visit_repeat(&mut self, s: &Repeat) -> T;
+    fn visit_loop(&mut self, s: &Loop) -> T;
+    fn visit_break(&mut self, s: &Break) -> T;
+    fn visit_continue(&mut self, s: &Continue) -> T;
+    fn visit_throw(&mut self, s: &Throw) -> T;
+    fn visit_try(&mut self, s: &Try) -> T;
+    fn visit_assert(&mut self, s: &Assert) -> T;
+    fn visit_return(&mut self, s: &Return) -> T;
 }
 
-pub struct Stmt {
-    s_type: StmtType,
+impl Stmt {
+    pub fn node(&self) -> &Node {
+	match self {
+	    Stmt::Print(s) => &s.node,
+	    Stmt::Expression(s) => &s.node,
+	    Stmt::TypedDecl(s) => &s.node,
+	    Stmt::VarDecl(s) => &s.node,
+	    Stmt::Decls(s) => &s.node,
+	    Stmt::FnDecl(s) => &s.node,
+	    Stmt::Block(s) => &s.node,
+	    Stmt::Class(s) => &s.node,
+	    Stmt::Interface(s) => &s.node,
+	    Stmt::If(s) => &s.node,
+	    Stmt::While(s) => &s.node,
+	    Stmt::Repeat(s) => &s.node,
+	    Stmt::Loop(s) => &s.node,
+	    Stmt::ForIn(s) => &s.node,
+	    Stmt::Break(s) => &s.node,
+	    Stmt::Continue(s) => &s.node,
+	    Stmt::Throw(s) => &s.node,
+	    Stmt::Try(s) => &s.node,
+	    Stmt::Assert(s) => &s.node,
+	    Stmt::Return(s) => &s.node,
+	}
+    }
+
+    fn node_mut(&mut self) -> &mut Node {
+	match self {
+	    Stmt::Print(s) => &mut s.node,
+	    Stmt::Expression(s) => &mut s.node,
+	    Stmt::TypedDecl(s) => &mut s.node,
+	    Stmt::VarDecl(s) => &mut s.node,
+	    Stmt::Decls(s) => &mut s.node,
+	    Stmt::FnDecl(s) => &mut s.node,
+	    Stmt::Block(s) => &mut s.node,
+	    Stmt::Class(s) => &mut s.node,
+	    Stmt::Interface(s) => &mut s.node,
+	    Stmt::If(s) => &mut s.node,
+	    Stmt::While(s) => &mut s.node,
+	    Stmt::Repeat(s) => &mut s.node,
+	    Stmt::Loop(s) => &mut s.node,
+	    Stmt::ForIn(s) => &mut s.node,
+	    Stmt::Break(s) => &mut s.node,
+	    Stmt::Continue(s) => &mut s.node,
+	    Stmt::Throw(s) => &mut s.node,
+	    Stmt::Try(s) => &mut s.node,
+	    Stmt::Assert(s) => &mut s.node,
+	    Stmt::Return(s) => &mut s.node,
+	}
+    }
+
+    pub fn span(&self) -> Span {
+	self.node().span
+    }
+
+    //where it starts, 0 for one the parser made up
+    pub fn line(&self) -> u32 {
+	self.span().line
+    }
+
+    pub fn at(mut self, span: Span) -> Self {
+	self.node_mut().span = span;
+	self
+    }
+
+    //the keyword, if control never goes on to the statement after this one
+    pub fn jump(&self) -> Option<&'static str> {
+	match self {
+	    Stmt::Return(..) => Some("return"),
+	    Stmt::Break(..) => Some("break"),
+	    Stmt::Continue(..) => Some("continue"),
+	    Stmt::Throw(..) => Some("throw"),
+	    _ => None,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	match self {
+	    Stmt::Print(s) => s.print(),
+	    Stmt::Expression(s) => s.print(),
+	    Stmt::TypedDecl(s) => s.print(),
+	    Stmt::VarDecl(s) => s.print(),
+	    Stmt::Decls(s) => s.print(),
+	    Stmt::FnDecl(s) => s.print(),
+	    Stmt::Block(s) => s.print(),
+	    Stmt::Class(s) => s.print(),
+	    Stmt::Interface(s) => s.print(),
+	    Stmt::If(s) => s.print(),
+	    Stmt::While(s) => s.print(),
+	    Stmt::Repeat(s) => s.print(),
+	    Stmt::Loop(s) => s.print(),
+	    Stmt::ForIn(s) => s.print(),
+	    Stmt::Break(s) => s.print(),
+	    Stmt::Continue(s) => s.print(),
+	    Stmt::Throw(s) => s.print(),
+	    Stmt::Try(s) => s.print(),
+	    Stmt::Assert(s) => s.print(),
+	    Stmt::Return(s) => s.print(),
+	}
+    }
+
+    //the names this declares into the scope it's in, a block
+    //declares nothing since it has a scope of its own
+    pub fn declares(&self) -> Vec<&Token> {
+	match self {
+	    Stmt::TypedDecl(s) => vec![&s.name],
+	    Stmt::FnDecl(s) => vec![&s.name],
+	    Stmt::Class(s) => vec![&s.name],
+	    Stmt::VarDecl(s) => s.pattern.names(),
+	    Stmt::Decls(s) => s.decls.iter().flat_map(|d| d.declares()).collect(),
+	    _ => Vec::new(),
+	}
+    }
+
+    pub fn accept<T, V: StmtVisitor<T> + ?Sized>(&self, visitor: &mut V) -> T {
+	match self {
+	    Stmt::Print(s) => visitor.visit_print(s),
+	    Stmt::Expression(s) => visitor.visit_expression(s),
+	    Stmt::TypedDecl(s) => visitor.visit_typed_decl(s),
+	    Stmt::VarDecl(s) => visitor.visit_vardecl(s),
+	    Stmt::Decls(s) => visitor.visit_decls(s),
+	    Stmt::FnDecl(s) => visitor.visit_fndecl(s),
+	    Stmt::Block(s) => visitor.visit_block(s),
+	    Stmt::Class(s) => visitor.visit_class(s),
+	    Stmt::Interface(s) => visitor.visit_interface(s),
+	    Stmt::If(s) => visitor.visit_if(s),
+	    Stmt::While(s) => visitor.visit_while(s),
+	    Stmt::ForIn(s) => visitor.visit_forin(s),
+	    Stmt::Repeat(s) => visitor.visit_repeat(s),
+	    Stmt::Loop(s) => visitor.visit_loop(s),
+	    Stmt::Break(s) => visitor.visit_break(s),
+	    Stmt::Continue(s) => visitor.visit_continue(s),
+	    Stmt::Throw(s) => visitor.visit_throw(s),
+	    Stmt::Try(s) => visitor.visit_try(s),
+	    Stmt::Assert(s) => visitor.visit_assert(s),
+	    Stmt::Return(s) => visitor.visit_return(s),
+	}
+    }
 }
 
-impl Stmt {
-    pub fn new(s_type: StmtType) -> Self {
-	Stmt {
-	    s_type: s_type,
-	}
-    }
-
-    pub fn print(&self) -> String {
-	use StmtType::*;
-	match &self.s_type {
-	    Print(e) => {
-		format!("(print {})", e.print())
-	    },
-	    Expression(e) => {
-		format!("{}", e.print())
-	    },
-	    IntDecl(n, e) => {
-		format!("(int {n}{}", match e {
-		    Some(ex) => format!(" {})", ex.print()),
-		    None => format!(")"),
-		})
-	    },
-	    RealDecl(n, e) => {
-		format!("(real {n}{}", match e {
-		    Some(ex) => format!(" {})", ex.print()),
-		    None => format!(")"),
-		})
-	    },
-	    StrDecl(n, e) => {
-		format!("(str {n}{}", match e {
-		    Some(ex) => format!(" {})", ex.print()),
-		    None => format!(")"),
-		})
-	    },
-	    Block(s) => {
-		let mut output = String::new();
-		output.push_str("(block\n");
-		for stmt in s {
-		    output.push_str(&stmt.print());
-		    output.push('\n');
-		}
-		output.push(')');
-		output
-	    },
-	    If(c, t, e) => {
-		let mut output = String::new();
-		output.push_str("(if ");
-		output.push_str(&format!("{}\n{}\n", c.print(), t.print()));
-		output.push_str(&match e {
-		    Some(el) => format!("(else \n{}\n))", el.print()),
-		    None => format!(")"),
-		});
-		output
-	    },
-	    While(c, s) => {
-		format!("(while {}\n{}\n)", c.print(), s.print())
-	    },
-	}
-    }
-
-    pub fn accept(&self, visitor: &mut dyn StmtVisitor) -> Result<(), Box<dyn Error>> {
-	use StmtType::*;
-	match &self.s_type {
-	    Print(..) => {
-		visitor.visit_print(&self.s_type)
-	    },
-	    Expression(..) => {
-		visitor.visit_expression(&self.s_type)
-	    },
-	    IntDecl(..) => {
-		visitor.visit_intdecl(&self.s_type)
-	    },
-	    RealDecl(..) => {
-		visitor.visit_realdecl(&self.s_type)
-	    },
-	    StrDecl(..) => {
-		visitor.visit_strdecl(&self.s_type)
-	    },
-	    Block(..) => {
-		visitor.visit_block(&self.s_type)
-	    },
-	    If(..) => {
-		visitor.visit_if(&self.s_type)
-	    }
-	    While(..) => {
-		visitor.visit_while(&self.s_type)
-	    },
+//the parser only knows a statement's span once it's read all of it, so
+//every statement starts out with an empty one and Stmt::at fills it in
+fn node() -> Node {
+    Node::new(Span::default())
+}
+
+#[derive (Clone)]
+pub struct Print {
+    pub node: Node,
+    pub expr: Box<Expr>,
+}
+
+impl Print {
+    pub fn new(expr: Box<Expr>) -> Self {
+	Print {
+	    node: node(),
+	    expr,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(print {})", self.expr.print())
+    }
+}
+
+#[derive (Clone)]
+pub struct Expression {
+    pub node: Node,
+    pub expr: Box<Expr>,
+}
+
+impl Expression {
+    pub fn new(expr: Box<Expr>) -> Self {
+	Expression {
+	    node: node(),
+	    expr,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	self.expr.print()
+    }
+}
+
+//an int, real, str or char declaration
+#[derive (Clone)]
+pub struct TypedDecl {
+    pub node: Node,
+    pub d_type: ValType,
+    pub name: Token,
+    pub init: Option<Box<Expr>>,
+}
+
+impl TypedDecl {
+    pub fn new(d_type: ValType, name: Token, init: Option<Box<Expr>>) -> Self {
+	TypedDecl {
+	    node: node(),
+	    d_type,
+	    name,
+	    init,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("({} {}{}", self.d_type, self.name.lexeme, match &self.init {
+	    Some(ex) => format!(" {})", ex.print()),
+	    None => ")".to_string(),
+	})
+    }
+}
+
+//the type comes from the value, so always initialized
+#[derive (Clone)]
+pub struct VarDecl {
+    pub node: Node,
+    pub pattern: Pattern,
+    pub init: Box<Expr>,
+}
+
+impl VarDecl {
+    pub fn new(pattern: Pattern, init: Box<Expr>) -> Self {
+	VarDecl {
+	    node: node(),
+	    pattern,
+	    init,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(var {} {})", self.pattern.print(), self.init.print())
+    }
+}
+
+//several declarations from one statement, in the current scope unlike a block
+#[derive (Clone)]
+pub struct Decls {
+    pub node: Node,
+    pub decls: Vec<Stmt>,
+}
+
+impl Decls {
+    pub fn new(decls: Vec<Stmt>) -> Self {
+	Decls {
+	    node: node(),
+	    decls,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let decls: Vec<String> = self.decls.iter().map(|d| d.print()).collect();
+	decls.join("\n")
+    }
+}
+
+//no sensible default for a function, so always initialized
+#[derive (Clone)]
+pub struct FnDecl {
+    pub node: Node,
+    pub name: Token,
+    pub init: Box<Expr>,
+}
+
+impl FnDecl {
+    pub fn new(name: Token, init: Box<Expr>) -> Self {
+	FnDecl {
+	    node: node(),
+	    name,
+	    init,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(fn {} {})", self.name.lexeme, self.init.print())
+    }
+}
+
+#[derive (Clone)]
+pub struct Block {
+    pub node: Node,
+    pub stmts: Vec<Stmt>,
+}
+
+impl Block {
+    pub fn new(stmts: Vec<Stmt>) -> Self {
+	Block {
+	    node: node(),
+	    stmts,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let mut output = String::new();
+	output.push_str("(block\n");
+	for stmt in &self.stmts {
+	    output.push_str(&stmt.print());
+	    output.push('\n');
+	}
+	output.push(')');
+	output
+    }
+}
+
+#[derive (Clone)]
+pub struct Class {
+    pub node: Node,
+    pub name: Token,
+    //a Variable
+    pub superclass: Option<Box<Expr>>,
+    pub interfaces: Vec<Token>,
+    pub methods: Vec<Rc<FnDef>>,
+}
+
+impl Class {
+    pub fn new(name: Token, superclass: Option<Box<Expr>>,
+	       interfaces: Vec<Token>, methods: Vec<Rc<FnDef>>) -> Self {
+	Class {
+	    node: node(),
+	    name,
+	    superclass,
+	    interfaces,
+	    methods,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let mut output = String::new();
+	output.push_str(&format!("(class {}", self.name.lexeme));
+	if let Some(s) = &self.superclass {
+	    output.push_str(&format!(" < {}", s.print()));
+	}
+	if !self.interfaces.is_empty() {
+	    let names: Vec<String> = self.interfaces.iter().map(|i| i.lexeme.to_string()).collect();
+	    output.push_str(&format!(" implements {}", names.join(", ")));
+	}
+	output.push('\n');
+	for def in &self.methods {
+	    let name = def.name.as_ref().map(|n| n.lexeme.to_string()).unwrap_or_default();
+	    output.push_str(&format!("(method {} {})\n", name, def.print()));
+	}
+	output.push(')');
+	output
+    }
+}
+
+//only checked by the parser, there's nothing to run
+#[derive (Clone)]
+pub struct Interface {
+    pub node: Node,
+    pub name: Token,
+    pub methods: Vec<Rc<MethodSig>>,
+}
+
+impl Interface {
+    pub fn new(name: Token, methods: Vec<Rc<MethodSig>>) -> Self {
+	Interface {
+	    node: node(),
+	    name,
+	    methods,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let mut output = String::new();
+	output.push_str(&format!("(interface {}\n", self.name.lexeme));
+	for sig in &self.methods {
+	    output.push_str(&sig.print());
+	    output.push('\n');
 	}
+	output.push(')');
+	output
+    }
+}
+
+#[derive (Clone)]
+pub struct If {
+    pub node: Node,
+    pub keyword: Token,
+    pub cond: Box<Expr>,
+    pub then_branch: Box<Stmt>,
+    pub else_branch: Option<Box<Stmt>>,
+}
+
+impl If {
+    pub fn new(keyword: Token, cond: Box<Expr>,
+	       then_branch: Box<Stmt>, else_branch: Option<Box<Stmt>>) -> Self {
+	If {
+	    node: node(),
+	    keyword,
+	    cond,
+	    then_branch,
+	    else_branch,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let mut output = String::new();
+	output.push_str("(if ");
+	output.push_str(&format!("{}\n{}\n", self.cond.print(), self.then_branch.print()));
+	output.push_str(&match &self.else_branch {
+	    Some(el) => format!("(else \n{}\n))", el.print()),
+	    None => ")".to_string(),
+	});
+	output
+    }
+}
+
+//loops all carry their label, if they have one
+#[derive (Clone)]
+pub struct While {
+    pub node: Node,
+    pub keyword: Token,
+    pub label: Option<Token>,
+    pub cond: Box<Expr>,
+    pub body: Box<Stmt>,
+    //the increment of a desugared c style for,
+    //kept separate so continue doesn't skip it
+    pub increment: Option<Box<Expr>>,
+}
+
+impl While {
+    pub fn new(keyword: Token, label: Option<Token>, cond: Box<Expr>,
+	       body: Box<Stmt>, increment: Option<Box<Expr>>) -> Self {
+	While {
+	    node: node(),
+	    keyword,
+	    label,
+	    cond,
+	    body,
+	    increment,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	match &self.increment {
+	    Some(i) => format!("({}while {}\n{}\n{}\n)", label(&self.label), self.cond.print(),
+			       self.body.print(), i.print()),
+	    None => format!("({}while {}\n{}\n)", label(&self.label), self.cond.print(), self.body.print()),
+	}
+    }
+}
+
+#[derive (Clone)]
+pub struct Repeat {
+    pub node: Node,
+    pub keyword: Token,
+    pub label: Option<Token>,
+    pub body: Box<Stmt>,
+    pub cond: Box<Expr>,
+}
+
+impl Repeat {
+    pub fn new(keyword: Token, label: Option<Token>, body: Box<Stmt>, cond: Box<Expr>) -> Self {
+	Repeat {
+	    node: node(),
+	    keyword,
+	    label,
+	    body,
+	    cond,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("({}repeat\n{}\n{})", label(&self.label), self.body.print(), self.cond.print())
+    }
+}
+
+#[derive (Clone)]
+pub struct Loop {
+    pub node: Node,
+    pub keyword: Token,
+    pub label: Option<Token>,
+    pub body: Box<Stmt>,
+}
+
+impl Loop {
+    pub fn new(keyword: Token, label: Option<Token>, body: Box<Stmt>) -> Self {
+	Loop {
+	    node: node(),
+	    keyword,
+	    label,
+	    body,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("({}loop\n{}\n)", label(&self.label), self.body.print())
+    }
+}
+
+//for x in ..., the body runs in a fresh scope with x bound each time around
+#[derive (Clone)]
+pub struct ForIn {
+    pub node: Node,
+    pub name: Token,
+    pub label: Option<Token>,
+    pub iterable: Box<Expr>,
+    pub body: Vec<Stmt>,
+}
+
+impl ForIn {
+    pub fn new(name: Token, label: Option<Token>, iterable: Box<Expr>, body: Vec<Stmt>) -> Self {
+	ForIn {
+	    node: node(),
+	    name,
+	    label,
+	    iterable,
+	    body,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let mut output = String::new();
+	output.push_str(&format!("({}for {} {}\n", label(&self.label), self.name.lexeme, self.iterable.print()));
+	for stmt in &self.body {
+	    output.push_str(&stmt.print());
+	    output.push('\n');
+	}
+	output.push(')');
+	output
+    }
+}
+
+#[derive (Clone)]
+pub struct Break {
+    pub node: Node,
+    pub keyword: Token,
+    pub label: Option<Token>,
+}
+
+impl Break {
+    pub fn new(keyword: Token, label: Option<Token>) -> Self {
+	Break {
+	    node: node(),
+	    keyword,
+	    label,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	match &self.label {
+	    Some(l) => format!("(break {})", l.lexeme),
+	    None => "(break)".to_string(),
+	}
+    }
+}
+
+#[derive (Clone)]
+pub struct Continue {
+    pub node: Node,
+    pub keyword: Token,
+    pub label: Option<Token>,
+}
+
+impl Continue {
+    pub fn new(keyword: Token, label: Option<Token>) -> Self {
+	Continue {
+	    node: node(),
+	    keyword,
+	    label,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	match &self.label {
+	    Some(l) => format!("(continue {})", l.lexeme),
+	    None => "(continue)".to_string(),
+	}
+    }
+}
+
+#[derive (Clone)]
+pub struct Throw {
+    pub node: Node,
+    pub keyword: Token,
+    pub value: Box<Expr>,
+}
+
+impl Throw {
+    pub fn new(keyword: Token, value: Box<Expr>) -> Self {
+	Throw {
+	    node: node(),
+	    keyword,
+	    value,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(throw {})", self.value.print())
+    }
+}
+
+//try ... catch name ... end
+#[derive (Clone)]
+pub struct Try {
+    pub node: Node,
+    pub body: Vec<Stmt>,
+    pub name: Token,
+    pub handler: Vec<Stmt>,
+}
+
+impl Try {
+    pub fn new(body: Vec<Stmt>, name: Token, handler: Vec<Stmt>) -> Self {
+	Try {
+	    node: node(),
+	    body,
+	    name,
+	    handler,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	let mut output = String::new();
+	output.push_str("(try\n");
+	for stmt in &self.body {
+	    output.push_str(&stmt.print());
+	    output.push('\n');
+	}
+	output.push_str(&format!("(catch {}\n", self.name.lexeme));
+	for stmt in &self.handler {
+	    output.push_str(&stmt.print());
+	    output.push('\n');
+	}
+	output.push_str("))");
+	output
+    }
+}
+
+#[derive (Clone)]
+pub struct Assert {
+    pub node: Node,
+    pub keyword: Token,
+    pub cond: Box<Expr>,
+    pub message: Option<Box<Expr>>,
+}
+
+impl Assert {
+    pub fn new(keyword: Token, cond: Box<Expr>, message: Option<Box<Expr>>) -> Self {
+	Assert {
+	    node: node(),
+	    keyword,
+	    cond,
+	    message,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	format!("(assert {}{}", self.cond.print(), match &self.message {
+	    Some(ex) => format!(" {})", ex.print()),
+	    None => ")".to_string(),
+	})
+    }
+}
+
+#[derive (Clone)]
+pub struct Return {
+    pub node: Node,
+    pub value: Option<Box<Expr>>,
+}
+
+impl Return {
+    pub fn new(value: Option<Box<Expr>>) -> Self {
+	Return {
+	    node: node(),
+	    value,
+	}
+    }
+
+    pub fn print(&self) -> String {
+	match &self.value {
+	    Some(ex) => format!("(return {})", ex.print()),
+	    None => "(return)".to_string(),
+	}
+    }
+}
+
+fn label(l: &Option<Token>) -> String {
+    match l {
+	Some(l) => format!("{}: ", l.lexeme),
+	None => String::new(),
     }
 }
@@ -1,13 +1,19 @@
 use std::fmt;
 use std::rc::Rc;
 
+//EOF is the name the parser has always matched on
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, Clone, PartialEq)]
 pub enum TokenType {
     LParen, RParen, Comma, Dot, Minus, Plus, Semicolon, Slash, Star,
+    Question, Colon, Arrow, Ellipsis, LBracket, RBracket, DotDot, DotDotEqual,
     Equal, EqualEqual, Less, LessEqual, Greater, GreaterEqual, Bang, BangEqual,
-    Ident, StrLit(Rc<String>), RealLit(f32), IntLit(u32),
+    Ident, StrLit(Rc<String>), RealLit(f32), IntLit(u32), CharLit(char),
     Begin, End, Function, Return, If, Then, Else, For, While, Do,
-    True, False, And, Or, Nil, Print, Real, Int, Str, Bool,
+    True, False, And, Or, Nil, Print, Real, Int, Str, Bool, Char,
+    Try, Catch, Throw, Assert, Fn, In, Step,
+    Repeat, Until, Loop, Break, Continue, Var, Class, SelfKw, Super,
+    Interface, Implements, List, Tuple, Operator,
     EOF,
 }
 
@@ -17,27 +23,71 @@ impl fmt::Display for TokenType {
     }
 }
 
+//where something is in the source, start and end are offsets in chars,
+//all zero for something the parser made up (e.g. a desugared for)
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: u32,
+}
+
+impl Span {
+    //from the start of whichever is first to the end of whichever is last
+    pub fn to(self, other: Span) -> Span {
+	if other == Span::default() {
+	    return self;
+	}
+	if self == Span::default() || other.start < self.start {
+	    return Span {
+		end: other.end.max(self.end),
+		..other
+	    };
+	}
+	Span {
+	    end: self.end.max(other.end),
+	    ..self
+	}
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Token {
     pub t_type: TokenType,
     pub lexeme: Rc<String>,
     pub line: u32,
+    //filled in by the scanner, zero for a token the parser made up
+    pub start: usize,
+    pub end: usize,
 }
 
 impl Token {
     pub fn new(t_type: TokenType, lexeme: String, line: u32) -> Self {
 	Token {
-	    t_type: t_type,
+	    t_type,
 	    lexeme: Rc::new(lexeme),
-	    line: line,
+	    line,
+	    start: 0,
+	    end: 0,
 	}
     }
 
-    pub fn to_string(&self) -> String {
+    pub fn span(&self) -> Span {
+	Span {
+	    start: self.start,
+	    end: self.end,
+	    line: self.line,
+	}
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
 	match &self.t_type {
-	    TokenType::RealLit(r) => format!("{r}"),
-	    TokenType::StrLit(s) => format!("{}", s),
-	    _ => format!("{}", self.lexeme),
+	    TokenType::RealLit(r) => write!(f, "{r}"),
+	    TokenType::StrLit(s) => write!(f, "{s}"),
+	    TokenType::CharLit(c) => write!(f, "{c}"),
+	    _ => write!(f, "{}", self.lexeme),
 	}
     }
 }
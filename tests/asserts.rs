@@ -0,0 +1,22 @@
+mod common;
+use common::{run_script, run_script_with};
+
+//a failed assert has its own exit status, and nothing after it runs
+#[test]
+fn failure_exit_status() {
+    let out = run_script("assert_fail.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(2), "{stdout}");
+    assert!(stdout.contains("4: Error (assert): assertion (== (+ x 1) 3) failed: sums"), "{stdout}");
+    assert!(stdout.lines().any(|l| l == "1"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "2"), "{stdout}");
+}
+
+#[test]
+fn no_asserts() {
+    let out = run_script_with("assert_fail.it", &["--no-asserts"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    assert!(!stdout.contains("assertion"), "{stdout}");
+    assert!(stdout.lines().any(|l| l == "2"), "{stdout}");
+}
@@ -0,0 +1,16 @@
+mod common;
+use common::{run_script, run_script_with};
+
+#[test]
+fn non_ascii_literals() {
+    let out = run_script("chars_unicode.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn byte_chars_rejects_them() {
+    let out = run_script_with("chars_unicode.it", &["--byte-chars"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("6: Error : Character literal does not fit in a byte"), "{stdout}");
+}
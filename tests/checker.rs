@@ -0,0 +1,47 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn well_typed() {
+    let out = run_script("check_ok.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//every error is reported, and none of the script runs
+#[test]
+fn reports_every_type_error() {
+    let out = run_script("check_errors.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+    for expect in [
+	"2: Error  at 'a': can't initialize int 'a' with str",
+	"3: Error  at 'r': can't initialize real 'r' with int",
+	"4: Error  at 's': can't initialize str 's' with char",
+	"5: Error  at '+': can't apply '+' to int and real",
+	"6: Error  at 'if': conditional expression must be boolean, not int",
+	"9: Error  at 'while': conditional expression must be boolean, not str",
+	"12: Error  at '<': can't apply '<' to int and str",
+	"12: Error  at 'or': logical operand must be boolean, not int",
+	"13: Error  at 'function': function 'half' should return int but returns real",
+	"16: Error  at '3': argument 'x' should be real but got int",
+	"17: Error  at 'a': can't assign real to int 'a'",
+	"18: Error  at '-': can't apply '-' to int",
+	"19: Error  at '[': index must be int, not real",
+	"20: Error  at '(': can't call a value of type int",
+	"21: Error  at 'c': can't loop over a value of type int",
+	"25: Error  at '+': operator '+' isn't defined for Plain",
+	"26: Error  at 'assert': assertion must be boolean, not int",
+	"27: Error  at '?': conditional branches must have the same type, not int and str",
+	"29: Error  at '+': operator '+' isn't defined for Plain",
+	"30: Error  at '<': operator '<' isn't defined for Plain",
+	"31: Error  at '*': can't apply '*' to int and Plain",
+	"33: Error  at 'x': a value of type int has no properties",
+	"40: Error  at 'pet': argument 'd' should be Dog but got Plain",
+	"51: Error  at 'function': function 'wrong' should return str but returns int",
+    ] {
+	assert!(stdout.contains(expect), "missing {expect:?} in {stdout}");
+    }
+    //a subclass of Plain could implement Shape, so that's left for when it runs
+    assert!(!stdout.contains("should be Shape"), "{stdout}");
+}
@@ -0,0 +1,101 @@
+mod common;
+use common::{run_script, run_prompt};
+
+#[test]
+fn fields() {
+    let out = run_script("class_fields.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn variables_and_fields_keep_their_class() {
+    let out = run_script("class_assign_types.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn methods_init_and_binding() {
+    let out = run_script("class_methods.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn inheritance_and_super() {
+    let out = run_script("class_inherit.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn interfaces() {
+    let out = run_script("interface_calls.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn operators() {
+    let out = run_script("class_operators.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn operator_error_names_the_class() {
+    let out = run_script("class_operator_missing.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(!out.status.success());
+    assert!(stdout.contains("operator '+' isn't defined for Plain"), "{stdout}");
+}
+
+//the rest are parse errors, so there's no script left to check itself
+
+#[test]
+fn self_outside_class() {
+    let out = run_script("class_self_outside.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("can't use 'self' outside of a class"), "{stdout}");
+}
+
+#[test]
+fn super_without_superclass() {
+    let out = run_script("class_super_no_superclass.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("can't use 'super' in a class with no superclass"), "{stdout}");
+}
+
+#[test]
+fn init_returning_a_value() {
+    let out = run_script("class_init_return.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("can't return a value from init"), "{stdout}");
+}
+
+#[test]
+fn interface_missing_method() {
+    let out = run_script("interface_missing.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("class 'Sq' is missing method 'scale' of interface 'Shape'"), "{stdout}");
+}
+
+#[test]
+fn interface_wrong_signature() {
+    let out = run_script("interface_mismatch.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("method 'area' doesn't match interface 'Shape', expect () -> int"), "{stdout}");
+}
+
+//each line at the prompt is parsed on its own, but interfaces are remembered
+#[test]
+fn interfaces_across_prompt_lines() {
+    let out = run_prompt("interface P begin function f() -> int; end\n\
+			  class C implements P begin function f() -> int return 1; end end\n\
+			  print C().f();\n\
+			  class D implements P begin end\n");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.lines().any(|l| l == "1"), "{stdout}");
+    assert!(!stdout.contains("undefined interface"), "{stdout}");
+    assert!(stdout.contains("class 'D' is missing method 'f' of interface 'P'"), "{stdout}");
+}
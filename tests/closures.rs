@@ -0,0 +1,37 @@
+mod common;
+use common::{run_script, run_prompt};
+
+#[test]
+fn closures_outlive_their_call() {
+    let out = run_script("closure_upward.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn variables_found_through_static_links() {
+    let out = run_script("closure_links.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn closures_made_in_tail_calls() {
+    let out = run_script("closure_tail.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn closures_keep_self() {
+    let out = run_script("closure_methods.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//what links::analyze found about a function is kept for calls on later lines
+#[test]
+fn closures_called_on_later_prompt_lines() {
+    let out = run_prompt("function counter() -> fn int n = 0; function inc() -> int n = n + 1; return n; end return inc; end\n\
+			  fn c = counter();\n\
+			  c();\n\
+			  print c();\n");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.lines().any(|l| l == "2"), "{stdout}");
+}
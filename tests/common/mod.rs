@@ -0,0 +1,32 @@
+//each test file is its own crate and only uses some of these
+#![allow(dead_code)]
+
+use std::io::Write;
+use std::process::{Command, Output, Stdio};
+
+//run one of the scripts in tests/scripts through the interpreter,
+//the scripts check themselves with assert so the exit status is
+//usually all there is to look at
+pub fn run_script(name: &str) -> Output {
+    run_script_with(name, &[])
+}
+
+//the same with options before the script
+pub fn run_script_with(name: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_interptest"))
+	.args(args)
+	.arg(format!("{}/tests/scripts/{}", env!("CARGO_MANIFEST_DIR"), name))
+	.output()
+	.expect("couldn't run interptest")
+}
+
+//type lines in at the prompt, one run per line
+pub fn run_prompt(lines: &str) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_interptest"))
+	.stdin(Stdio::piped())
+	.stdout(Stdio::piped())
+	.spawn()
+	.expect("couldn't run interptest");
+    child.stdin.take().expect("no stdin").write_all(lines.as_bytes()).expect("couldn't write to interptest");
+    child.wait_with_output().expect("interptest didn't finish")
+}
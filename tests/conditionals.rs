@@ -0,0 +1,25 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn both_forms() {
+    let out = run_script("conditionals.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//the checker reports branches of different types, and none of the script runs
+#[test]
+fn branch_types() {
+    let out = run_script("conditional_types.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+    for expect in [
+	"4: Error  at '?': conditional branches must have the same type, not int and str",
+	"5: Error  at 'if': conditional branches must have the same type, not real and char",
+	"6: Error  at '?': conditional branches must have the same type, not nil and str",
+	"7: Error  at '?': conditional branches must have the same type, not int and list<int>",
+    ] {
+	assert!(stdout.contains(expect), "missing {expect:?} in {stdout}");
+    }
+}
@@ -0,0 +1,43 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn declare_unpack_and_swap() {
+    let out = run_script("destructure.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//every name in a declaration list is checked, and none of the script runs
+#[test]
+fn declaration_types() {
+    let out = run_script("destructure_types.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+    for expect in [
+	"2: Error  at 'b': can't initialize int 'b' with str",
+	"2: Error  at 'c': can't initialize int 'c' with real",
+	"3: Error  at 't': can't initialize str 't' with char",
+	"4: Error  at 'function': function 'pair' should return tuple but returns int",
+    ] {
+	assert!(stdout.contains(expect), "missing {expect:?} in {stdout}");
+    }
+}
+
+#[test]
+fn typed_name_in_pattern() {
+    let out = run_script("destructure_typed_name.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("2: Error (TypeError): mismatched types y and IntVal(2) at 'y'"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
+
+#[test]
+fn wrong_count() {
+    let out = run_script("destructure_count.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("3: Error (TypeError): can't unpack a tuple of 3 into 2 names at ','"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
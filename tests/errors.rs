@@ -0,0 +1,8 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn caught_error_properties() {
+    let out = run_script("catch_kinds.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
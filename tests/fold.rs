@@ -0,0 +1,42 @@
+mod common;
+use common::run_script;
+
+//the tree is printed before it runs, so what was folded shows up there
+#[test]
+fn folds_constants() {
+    let out = run_script("fold.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    for line in ["(int a 17)", "(real r 6)", "(var b true)", "(int c 5)", "(int code 66)", "(var t true)"] {
+	assert!(stdout.lines().any(|l| l == line), "missing {line}\n{stdout}");
+    }
+}
+
+#[test]
+fn keeps_what_fails() {
+    let out = run_script("fold.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    for line in ["(int big (+ 4294967295 1))", "(int q (/ 1 0))", "(real q (/ 1 0))"] {
+	assert!(stdout.lines().any(|l| l == line), "missing {line}\n{stdout}");
+    }
+}
+
+#[test]
+fn drops_dead_branches() {
+    let out = run_script("fold.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(stdout.lines().any(|l| l == "(print 111)"), "{stdout}");
+    for gone in ["222", "333", "444", "(if", "(while"] {
+	assert!(!stdout.contains(gone), "{gone} is still there\n{stdout}");
+    }
+}
+
+//folding comes after the checks, so a branch that never runs is still checked
+#[test]
+fn checks_dead_branches() {
+    let out = run_script("fold_dead_checked.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("3: Error  at 'undefined_name': undefined variable"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
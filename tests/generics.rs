@@ -0,0 +1,26 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn generic_functions_and_lists() {
+    let out = run_script("generic_calls.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//arguments the checker knows the types of are checked before anything runs
+
+#[test]
+fn mismatched_type_parameter() {
+    let out = run_script("generic_mismatch.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("argument 'b' should be int, since T is int in this call to 'max', but got str"), "{stdout}");
+}
+
+#[test]
+fn unmet_bound() {
+    let out = run_script("generic_bound.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("T is int in this call to 'biggest', which isn't Sized"), "{stdout}");
+}
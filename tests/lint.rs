@@ -0,0 +1,35 @@
+mod common;
+use common::run_script_with;
+
+//lint mode only gives warnings, the script doesn't run
+#[test]
+fn every_lint() {
+    let out = run_script_with("lint_warnings.it", &["--lint"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    let warnings: Vec<&str> = stdout.lines().collect();
+    assert_eq!(warnings, [
+	"3: Warning at 'unused_local': 'unused_local' is never used [unused]",
+	"4: Warning at 'total': 'total' shadows the one declared on line 1 [shadow]",
+	"9: Warning: unreachable code after return [unreachable]",
+	"11: Warning at 'while': loop body never runs [while-false]",
+	"14: Warning at 'if': condition is always true [constant-condition]",
+	"19: Warning: unreachable code after break [unreachable]",
+	"26: Warning: unknown lint 'unsued' in pragma",
+    ]);
+}
+
+#[test]
+fn lints_can_be_picked() {
+    let out = run_script_with("lint_warnings.it", &["--lint=unused,shadow", "--allow=shadow"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let warnings: Vec<&str> = stdout.lines().filter(|l| l.contains("[")).collect();
+    assert_eq!(warnings, ["3: Warning at 'unused_local': 'unused_local' is never used [unused]"]);
+}
+
+#[test]
+fn unknown_lint_on_the_command_line() {
+    let out = run_script_with("lint_warnings.it", &["--lint=unusd"]);
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("unknown lint 'unusd'"));
+}
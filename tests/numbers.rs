@@ -0,0 +1,19 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn literals() {
+    let out = run_script("numbers.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//every literal that doesn't fit is reported and nothing runs
+#[test]
+fn out_of_range() {
+    let out = run_script("numbers_out_of_range.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert_eq!(stdout.matches("integer literal out of range").count(), 2, "{stdout}");
+    assert!(stdout.contains("real literal out of range"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
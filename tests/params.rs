@@ -0,0 +1,33 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn defaults_named_and_variadic() {
+    let out = run_script("params.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//the script catches each bad call and checks the message and line
+#[test]
+fn argument_errors() {
+    let out = run_script("param_errors.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn positional_after_named() {
+    let out = run_script("param_order.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("6: Error  at '1': positional argument after a named one"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
+
+//an error nothing catches says the same, with where it is
+#[test]
+fn uncaught_missing_argument() {
+    let out = run_script("param_missing.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("6: Error (ArgumentError): missing argument 'x' in call to 'scale' at '('"), "{stdout}");
+}
@@ -0,0 +1,64 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn scopes() {
+    let out = run_script("resolve_scopes.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//only a call that reaches the function itself checks its var parameters
+#[test]
+fn shadowed_function_var_args() {
+    let out = run_script("resolve_var_args.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+//the errors are found before anything runs, so the print never happens
+
+#[test]
+fn own_initializer() {
+    let out = run_script("resolve_own_init.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("use of variable in its own initializer"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
+
+#[test]
+fn undefined_variable() {
+    let out = run_script("resolve_undefined.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("Error  at 'missing': undefined variable"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
+
+#[test]
+fn duplicate_declaration() {
+    let out = run_script("resolve_duplicate.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("duplicate declaration in same scope"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
+
+#[test]
+fn reports_every_error() {
+    let out = run_script("resolve_all_errors.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("2: Error  at 'a': duplicate declaration in same scope"), "{stdout}");
+    assert!(stdout.contains("3: Error  at 'b': undefined variable"), "{stdout}");
+    assert!(stdout.contains("4: Error  at 'c': use of variable in its own initializer"), "{stdout}");
+}
+
+#[test]
+fn var_argument_not_a_variable() {
+    let out = run_script("resolve_var_arg_value.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(stdout.contains("8: Error  at 'outer': argument for var parameter 'a' of 'bump' must be a variable"), "{stdout}");
+    assert!(stdout.contains("10: Error  at '2': argument for var parameter 'a' of 'bump' must be a variable"), "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
@@ -0,0 +1,31 @@
+mod common;
+use common::{run_script, run_script_with};
+
+#[test]
+fn assigned_on_every_path() {
+    let out = run_script_with("strict_ok.it", &["--strict"]);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn reports_every_unassigned_read() {
+    let out = run_script_with("strict_errors.it", &["--strict"]);
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert_eq!(out.status.code(), Some(1));
+    for (line, name) in [(6, "a"), (13, "b"), (19, "c"), (26, "d"), (34, "e"),
+			 (46, "f"), (51, "g"), (64, "h"), (68, "k")] {
+	let msg = format!("{line}: Error  at '{name}': '{name}' might be read before it's assigned");
+	assert!(stdout.contains(&msg), "missing {msg}\n{stdout}");
+    }
+    assert_eq!(stdout.matches("might be read").count(), 9, "{stdout}");
+    assert!(!stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
+
+//without --strict the same script runs with the zero defaults
+#[test]
+fn defaults_without_strict() {
+    let out = run_script("strict_errors.it");
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    assert!(out.status.success(), "{stdout}");
+    assert!(stdout.lines().any(|l| l == "12345"), "{stdout}");
+}
@@ -0,0 +1,20 @@
+mod common;
+use common::run_script;
+
+#[test]
+fn self_recursion_million_deep() {
+    let out = run_script("tail_self.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn mutual_recursion_million_deep() {
+    let out = run_script("tail_mutual.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}
+
+#[test]
+fn tail_call_keeps_return_type_check() {
+    let out = run_script("tail_return_type.it");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stdout));
+}